@@ -1,4 +1,6 @@
+use crate::dates::{self, DateFormat};
 use crate::error::{Result, TlError};
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
@@ -10,20 +12,15 @@ pub enum FileMode {
     Fixed,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 pub enum InsertPosition {
     #[serde(rename = "top")]
     Top,
     #[serde(rename = "bottom")]
+    #[default]
     Bottom,
 }
 
-impl Default for InsertPosition {
-    fn default() -> Self {
-        Self::Bottom
-    }
-}
-
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FileEntry {
     pub path: String,
@@ -115,10 +112,30 @@ impl Config {
         }
     }
 
+    /// The configured `date_format`, translated for writing and parsing dates.
+    pub fn date_fmt(&self) -> Result<DateFormat> {
+        DateFormat::new(&self.date_format)
+    }
+
+    /// Parse a date typed by a person or an agent: "today", the configured
+    /// format, ISO-8601 or DD/MM/YYYY.
+    pub fn parse_date_arg(&self, s: &str) -> Result<NaiveDate> {
+        if s == "today" {
+            return Ok(dates::today());
+        }
+        self.date_fmt()?.parse(s).ok_or_else(|| {
+            TlError::Parse(format!("invalid date '{}' (expected {})", s, self.date_format))
+        })
+    }
+
     pub fn state_path() -> PathBuf {
         Self::base_dir().join("state.json")
     }
 
+    pub fn index_path() -> PathBuf {
+        Self::base_dir().join("index.json")
+    }
+
     pub fn lock_path() -> PathBuf {
         Self::base_dir().join("lock")
     }
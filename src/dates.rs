@@ -0,0 +1,177 @@
+use crate::error::{Result, TlError};
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime};
+
+/// Time-of-day part of completion and note timestamps (e.g. "09:41AM").
+const TIME_FMT: &str = "%I:%M%p";
+
+/// ISO-8601 dates are always accepted when parsing, whatever the configured format.
+const ISO_FMT: &str = "%Y-%m-%d";
+
+/// The format `tl` wrote before `date_format` was honored. Accepted when parsing
+/// so logs started under the old default keep working after switching formats.
+const LEGACY_FMT: &str = "%d/%m/%Y";
+
+/// A `date_format` from config (e.g. "DD/MM/YYYY", "YYYY-MM-DD") translated
+/// into a chrono format string used for both writing and parsing dates.
+#[derive(Debug, Clone)]
+pub struct DateFormat {
+    chrono: String,
+}
+
+impl DateFormat {
+    /// Translate a token spec into a chrono format.
+    ///
+    /// Tokens: `YYYY` (4-digit year), `YY` (2-digit year), `MM`/`DD` (zero-padded
+    /// month/day), `M`/`D` (unpadded month/day). Any non-letter character is
+    /// copied through as a literal separator.
+    pub fn new(spec: &str) -> Result<Self> {
+        let mut chrono = String::new();
+        let chars: Vec<char> = spec.chars().collect();
+        let mut i = 0;
+
+        while i < chars.len() {
+            let c = chars[i];
+            let run = chars[i..].iter().take_while(|&&x| x == c).count();
+            match (c, run) {
+                ('Y', 4) => chrono.push_str("%Y"),
+                ('Y', 2) => chrono.push_str("%y"),
+                ('M', 2) => chrono.push_str("%m"),
+                ('M', 1) => chrono.push_str("%-m"),
+                ('D', 2) => chrono.push_str("%d"),
+                ('D', 1) => chrono.push_str("%-d"),
+                ('%', _) => {
+                    chrono.push_str("%%");
+                    i += 1;
+                    continue;
+                }
+                _ if c.is_alphabetic() => {
+                    return Err(TlError::Config(format!(
+                        "unsupported token '{}' in date_format '{}' (use YYYY, YY, MM, M, DD, D)",
+                        c.to_string().repeat(run),
+                        spec
+                    )));
+                }
+                _ => {
+                    chrono.push(c);
+                    i += 1;
+                    continue;
+                }
+            }
+            i += run;
+        }
+
+        if !chrono.contains("%Y") && !chrono.contains("%y") {
+            return Err(TlError::Config(format!(
+                "date_format '{}' must contain a year (YYYY or YY)",
+                spec
+            )));
+        }
+
+        Ok(Self { chrono })
+    }
+
+    pub fn format(&self, date: NaiveDate) -> String {
+        date.format(&self.chrono).to_string()
+    }
+
+    /// Parse a date written in the configured format, falling back to ISO-8601
+    /// and then the legacy DD/MM/YYYY format. The configured format always wins,
+    /// so under MM/DD/YYYY an ambiguous legacy header like `07/03/2026` reads as
+    /// July 3rd; only dates that don't fit it (`25/12/2026`) reach the fallback.
+    pub fn parse(&self, text: &str) -> Option<NaiveDate> {
+        let text = text.trim();
+        [self.chrono.as_str(), ISO_FMT, LEGACY_FMT]
+            .iter()
+            .find_map(|f| NaiveDate::parse_from_str(text, f).ok())
+    }
+
+    /// Today's date in the configured format.
+    pub fn today_str(&self) -> String {
+        self.format(today())
+    }
+
+    /// Format a timestamp as "<date> <HH:MMAM>".
+    pub fn format_timestamp(&self, at: NaiveDateTime) -> String {
+        format!("{} {}", self.format(at.date()), at.format(TIME_FMT))
+    }
+
+    /// Parse a "<date> <HH:MMAM>" timestamp.
+    pub fn parse_timestamp(&self, text: &str) -> Option<NaiveDateTime> {
+        let (date, time) = text.trim().rsplit_once(' ')?;
+        let date = self.parse(date)?;
+        let time = NaiveTime::parse_from_str(time, TIME_FMT).ok()?;
+        Some(date.and_time(time))
+    }
+}
+
+/// The current local date and time.
+pub fn now() -> NaiveDateTime {
+    Local::now().naive_local()
+}
+
+/// Today's local date.
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn translates_tokens() {
+        let fmt = DateFormat::new("D.M.YY").unwrap();
+        assert_eq!(fmt.format(date(2026, 3, 7)), "7.3.26");
+        let fmt = DateFormat::new("YYYY-MM-DD").unwrap();
+        assert_eq!(fmt.format(date(2026, 3, 7)), "2026-03-07");
+    }
+
+    #[test]
+    fn rejects_bad_specs() {
+        assert!(DateFormat::new("DD/MM/YYYY hh").is_err());
+        assert!(DateFormat::new("DD/MM").is_err());
+    }
+
+    #[test]
+    fn parses_configured_format() {
+        let fmt = DateFormat::new("MM/DD/YYYY").unwrap();
+        assert_eq!(fmt.parse("12/25/2026"), Some(date(2026, 12, 25)));
+        assert_eq!(fmt.parse(" 12/25/2026 "), Some(date(2026, 12, 25)));
+    }
+
+    #[test]
+    fn unpadded_and_padded_are_the_same_date() {
+        let fmt = DateFormat::new("DD/MM/YYYY").unwrap();
+        assert_eq!(fmt.parse("7/3/2026"), fmt.parse("07/03/2026"));
+    }
+
+    #[test]
+    fn falls_back_to_iso_then_legacy() {
+        let fmt = DateFormat::new("MM/DD/YYYY").unwrap();
+        assert_eq!(fmt.parse("2026-03-07"), Some(date(2026, 3, 7)));
+        // Not a valid MM/DD date, so it is read as legacy DD/MM
+        assert_eq!(fmt.parse("25/12/2026"), Some(date(2026, 12, 25)));
+        assert_eq!(fmt.parse("Ideas"), None);
+    }
+
+    #[test]
+    fn ambiguous_dates_use_the_configured_order() {
+        let us = DateFormat::new("MM/DD/YYYY").unwrap();
+        assert_eq!(us.parse("07/03/2026"), Some(date(2026, 7, 3)));
+        let eu = DateFormat::new("DD/MM/YYYY").unwrap();
+        assert_eq!(eu.parse("07/03/2026"), Some(date(2026, 3, 7)));
+    }
+
+    #[test]
+    fn timestamps_round_trip() {
+        let fmt = DateFormat::new("YYYY-MM-DD").unwrap();
+        let at = date(2026, 3, 7).and_hms_opt(21, 5, 0).unwrap();
+        let text = fmt.format_timestamp(at);
+        assert_eq!(text, "2026-03-07 09:05PM");
+        assert_eq!(fmt.parse_timestamp(&text), Some(at));
+    }
+}
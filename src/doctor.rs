@@ -0,0 +1,305 @@
+use crate::config::Config;
+use crate::dates::DateFormat;
+use crate::error::Result;
+use crate::index::TaskIndex;
+use crate::lock::FileLock;
+use crate::parser;
+use crate::state::State;
+use crate::writer;
+use chrono::NaiveDate;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// A line in one of the log files.
+#[derive(Debug, Clone)]
+pub struct Location {
+    pub path: PathBuf,
+    /// Label of the file.
+    pub file: String,
+    /// 0-based line number.
+    pub line: usize,
+    pub text: String,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line + 1)
+    }
+}
+
+/// A problem found by `tl doctor`.
+#[derive(Debug)]
+pub enum Issue {
+    /// The same ID on more than one task line. `keep` is the oldest copy;
+    /// the fix gives each of `extra` a new number.
+    DuplicateId {
+        id: String,
+        keep: Location,
+        extra: Vec<Location>,
+    },
+    /// The state.json counter for a tag is below the highest number in use.
+    /// Harmless, since every allocation syncs the counter against the index
+    /// first, so it's reported as a note rather than a problem. The fix just
+    /// brings state.json up to date.
+    StaleCounter { tag: String, counter: u64, max: u64 },
+    /// A timestamped note line that isn't attached to any task.
+    OrphanNote(Location),
+    /// A line that looks like a task but doesn't parse as one.
+    MalformedTask(Location),
+}
+
+impl Issue {
+    /// Whether `fix` can repair this issue. Orphan notes and malformed lines
+    /// need a human to decide what they were meant to be.
+    pub fn is_fixable(&self) -> bool {
+        matches!(self, Issue::DuplicateId { .. } | Issue::StaleCounter { .. })
+    }
+
+    /// Whether this issue makes `tl doctor` fail. A stale counter doesn't.
+    pub fn is_problem(&self) -> bool {
+        !matches!(self, Issue::StaleCounter { .. })
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::DuplicateId { id, keep, extra } => {
+                let extra: Vec<String> = extra.iter().map(|l| l.to_string()).collect();
+                write!(f, "duplicate ID {}: {} (oldest), {}", id, keep, extra.join(", "))
+            }
+            Issue::StaleCounter { tag, counter, max } => write!(
+                f,
+                "note: state counter for '{}' is {}, but {}-{} exists (the next add skips past it)",
+                tag, counter, tag, max
+            ),
+            Issue::OrphanNote(loc) => {
+                write!(f, "note not attached to a task at {}: {}", loc, loc.text.trim())
+            }
+            Issue::MalformedTask(loc) => {
+                write!(f, "malformed task line at {}: {}", loc, loc.text.trim())
+            }
+        }
+    }
+}
+
+/// Scan the full history of every configured file and the state file.
+pub fn check() -> Result<Vec<Issue>> {
+    let config = Config::load()?;
+    let fmt = config.date_fmt()?;
+
+    // Counters first, so fixing them in order happens before any renumbering
+    let index = TaskIndex::open(&config)?;
+    let state = State::load()?;
+    let mut issues = stale_counters(&state, |tag| index.max_number(tag));
+
+    let mut seen = Vec::new();
+    for entry in config.effective_files() {
+        let path = entry.resolved_path();
+        if !path.exists() {
+            continue;
+        }
+        let content = std::fs::read_to_string(&path)?;
+        let (tasks, problems) = scan_file(&content, &path, &entry.label, &fmt);
+        seen.extend(tasks);
+        issues.extend(problems);
+    }
+    issues.extend(duplicates(seen));
+
+    Ok(issues)
+}
+
+/// Counters in `state` that are below the highest number `max` reports for
+/// their tag, in tag order.
+fn stale_counters(state: &State, max: impl Fn(&str) -> u64) -> Vec<Issue> {
+    let mut tags: Vec<&String> = state.tags.keys().collect();
+    tags.sort();
+    tags.into_iter()
+        .filter_map(|tag| {
+            let counter = state.tags[tag];
+            let max = max(tag);
+            (counter < max).then(|| Issue::StaleCounter {
+                tag: tag.clone(),
+                counter,
+                max,
+            })
+        })
+        .collect()
+}
+
+/// A task occurrence: its ID, its section date (for picking the oldest) and
+/// where it is.
+type Occurrence = (String, Option<NaiveDate>, Location);
+
+/// Look through one file's content for malformed task lines and orphan
+/// notes, and list every task in it.
+fn scan_file(
+    content: &str,
+    path: &Path,
+    label: &str,
+    fmt: &DateFormat,
+) -> (Vec<Occurrence>, Vec<Issue>) {
+    let lines: Vec<&str> = content.lines().collect();
+    let sections = parser::parse_log(content, usize::MAX, fmt);
+    let location = |line: usize| Location {
+        path: path.to_path_buf(),
+        file: label.to_string(),
+        line,
+        text: lines[line].to_string(),
+    };
+
+    let mut tasks = Vec::new();
+    let mut issues = Vec::new();
+    let mut attached = BTreeSet::new();
+    for task in sections.iter().flat_map(|s| &s.tasks) {
+        tasks.push((task.id(), task.section_date, location(task.line_number)));
+        attached.insert(task.line_number);
+        attached.extend(task.notes.iter().map(|n| n.line_number));
+    }
+
+    let first_header = lines.iter().position(|l| parser::is_section_header(l).is_some());
+    for (i, line) in lines.iter().enumerate() {
+        if attached.contains(&i) {
+            continue;
+        }
+        if parser::looks_like_task(line) && parser::parse_task_line(line, fmt).is_none() {
+            issues.push(Issue::MalformedTask(location(i)));
+            continue;
+        }
+        // Notes before the first header aren't tracked, so can't be orphaned
+        if first_header.is_none_or(|h| i < h) {
+            continue;
+        }
+        let stamped = parser::is_note_line(line)
+            .is_some_and(|(_, text)| parser::parse_note_text(&text, fmt).created_at.is_some());
+        if stamped {
+            issues.push(Issue::OrphanNote(location(i)));
+        }
+    }
+
+    (tasks, issues)
+}
+
+/// Group occurrences by ID and report each ID found more than once, keeping
+/// the oldest.
+fn duplicates(occurrences: Vec<Occurrence>) -> Vec<Issue> {
+    let mut seen: BTreeMap<String, Vec<(Option<NaiveDate>, Location)>> = BTreeMap::new();
+    for (id, date, loc) in occurrences {
+        seen.entry(id).or_default().push((date, loc));
+    }
+
+    let mut issues = Vec::new();
+    for (id, mut found) in seen {
+        if found.len() < 2 {
+            continue;
+        }
+        // Oldest first; undated (freeform) sections count as oldest, ties keep file order
+        found.sort_by_key(|(date, _)| *date);
+        let mut found = found.into_iter().map(|(_, loc)| loc);
+        let keep = found.next().expect("at least two occurrences");
+        issues.push(Issue::DuplicateId {
+            id,
+            keep,
+            extra: found.collect(),
+        });
+    }
+    issues
+}
+
+/// Repair a fixable issue, returning a line per change made (none if it was
+/// already fixed).
+pub fn fix(issue: &Issue) -> Result<Vec<String>> {
+    match issue {
+        Issue::DuplicateId { id, extra, .. } => extra
+            .iter()
+            .map(|loc| {
+                let new_id = writer::renumber_task(&loc.path, loc.line, id)?;
+                Ok(format!("renumbered {} at {} to {}", id, loc, new_id))
+            })
+            .collect(),
+        Issue::StaleCounter { tag, max, .. } => {
+            let _lock = FileLock::acquire()?;
+            let mut state = State::load()?;
+            if state.tags.get(tag).is_some_and(|c| c >= max) {
+                return Ok(Vec::new());
+            }
+            state.sync_min(tag, *max);
+            state.save()?;
+            Ok(vec![format!("raised the '{}' counter to {}", tag, max)])
+        }
+        Issue::OrphanNote(_) | Issue::MalformedTask(_) => Ok(Vec::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fmt() -> DateFormat {
+        DateFormat::new("YYYY-MM-DD").unwrap()
+    }
+
+    fn scan(content: &str, label: &str) -> (Vec<Occurrence>, Vec<Issue>) {
+        scan_file(content, Path::new(label), label, &fmt())
+    }
+
+    #[test]
+    fn duplicates_keep_the_oldest_copy() {
+        let (mut seen, _) = scan(
+            "### 2026-10-16\n\n- [ ] dev-1 newer\n- [ ] dev-2 only\n",
+            "work",
+        );
+        seen.extend(scan("### 2026-10-14\n\n- [ ] dev-1 older\n", "home").0);
+
+        let issues = duplicates(seen);
+        assert_eq!(issues.len(), 1);
+        let Issue::DuplicateId { id, keep, extra } = &issues[0] else {
+            panic!("expected a duplicate, got {:?}", issues[0]);
+        };
+        assert_eq!(id, "dev-1");
+        assert_eq!((keep.file.as_str(), keep.line), ("home", 2));
+        assert_eq!(extra.len(), 1);
+        assert_eq!((extra[0].file.as_str(), extra[0].line), ("work", 2));
+    }
+
+    #[test]
+    fn freeform_copies_count_as_oldest() {
+        let (seen, _) = scan(
+            "### Ideas\n\n- [ ] dev-1 freeform\n\n### 2026-10-01\n\n- [ ] dev-1 dated\n",
+            "work",
+        );
+        let issues = duplicates(seen);
+        let Issue::DuplicateId { keep, extra, .. } = &issues[0] else {
+            panic!("expected a duplicate, got {:?}", issues[0]);
+        };
+        assert_eq!(keep.line, 2);
+        assert_eq!(extra[0].line, 6);
+    }
+
+    #[test]
+    fn scanning_finds_malformed_tasks_and_orphan_notes() {
+        let (seen, issues) = scan(
+            "### 2026-10-16\n      - [2026-10-16 09:00AM] stray\n\n- [ ] dev-1 fine\n- [X] dev-2 wrong checkbox\n",
+            "work",
+        );
+        assert_eq!(seen.len(), 1);
+        assert_eq!(issues.len(), 2, "{:?}", issues);
+        assert!(matches!(&issues[0], Issue::OrphanNote(loc) if loc.line == 1));
+        assert!(matches!(&issues[1], Issue::MalformedTask(loc) if loc.line == 4));
+    }
+
+    #[test]
+    fn only_counters_below_the_max_are_stale() {
+        let mut state = State::default();
+        state.tags.insert("dev".to_string(), 3);
+        state.tags.insert("ops".to_string(), 5);
+        let issues = stale_counters(&state, |tag| if tag == "dev" { 4 } else { 5 });
+
+        assert_eq!(issues.len(), 1);
+        assert!(matches!(
+            &issues[0],
+            Issue::StaleCounter { tag, counter: 3, max: 4 } if tag == "dev"
+        ));
+    }
+}
@@ -31,3 +31,21 @@ pub enum TlError {
 }
 
 pub type Result<T> = std::result::Result<T, TlError>;
+
+impl TlError {
+    /// The variant name, as a stable machine-readable error code (e.g. for
+    /// MCP clients).
+    pub fn code(&self) -> &'static str {
+        match self {
+            TlError::Io(_) => "Io",
+            TlError::Config(_) => "Config",
+            TlError::State(_) => "State",
+            TlError::Parse(_) => "Parse",
+            TlError::DuplicateId(_) => "DuplicateId",
+            TlError::TaskNotFound(_) => "TaskNotFound",
+            TlError::Lock(_) => "Lock",
+            TlError::NotInitialized => "NotInitialized",
+            TlError::Other(_) => "Other",
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use crate::dates;
+use crate::parser::{Priority, Status, Task};
+use chrono::NaiveDate;
+
+/// Criteria for narrowing down a task list. Unset fields match everything.
+#[derive(Debug, Default)]
+pub struct TaskFilter {
+    /// Only tasks that aren't done or cancelled.
+    pub open: bool,
+    /// Only completed tasks.
+    pub done: bool,
+    /// Only tasks in this state.
+    pub status: Option<Status>,
+    pub tag: Option<String>,
+    /// Only tasks at or above this priority.
+    pub priority: Option<Priority>,
+    /// Only tasks in the file with this label.
+    pub file: Option<String>,
+    /// Only tasks from sections dated on or after this day.
+    pub since: Option<NaiveDate>,
+    /// Only tasks from sections dated on or before this day.
+    pub until: Option<NaiveDate>,
+    /// Only open tasks due before today.
+    pub overdue: bool,
+    /// Only tasks with this `#label`.
+    pub label: Option<String>,
+    /// Only tasks assigned to this `@person`.
+    pub assignee: Option<String>,
+}
+
+impl TaskFilter {
+    /// Check a task (and the label of the file it lives in) against the filter.
+    /// Tasks under freeform headers have no date and never match a date range.
+    pub fn matches(&self, file_label: &str, task: &Task) -> bool {
+        if (self.open && task.status.is_closed()) || (self.done && task.status != Status::Done) {
+            return false;
+        }
+        if self.status.is_some_and(|status| status != task.status) {
+            return false;
+        }
+        if self.tag.as_ref().is_some_and(|tag| *tag != task.tag) {
+            return false;
+        }
+        if self.priority.is_some_and(|min| task.priority < min) {
+            return false;
+        }
+        if self.overdue && !task.is_overdue(dates::today()) {
+            return false;
+        }
+        if self.label.as_ref().is_some_and(|label| !task.has_label(label)) {
+            return false;
+        }
+        if self.assignee.as_ref().is_some_and(|who| !task.has_assignee(who)) {
+            return false;
+        }
+        if self.file.as_ref().is_some_and(|file| file != file_label) {
+            return false;
+        }
+        if self.since.is_some() || self.until.is_some() {
+            let Some(date) = task.section_date else {
+                return false;
+            };
+            if self.since.is_some_and(|since| date < since)
+                || self.until.is_some_and(|until| date > until)
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
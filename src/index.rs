@@ -0,0 +1,183 @@
+use crate::config::{atomic_write, Config};
+use crate::dates::DateFormat;
+use crate::error::{Result, TlError};
+use crate::parser;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Where a task lives on disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub id: String,
+    /// 0-based line number of the task line.
+    pub line: usize,
+    /// Other task IDs mentioned in the title or notes.
+    pub links: Vec<LinkEntry>,
+}
+
+/// One mention of another task ID.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkEntry {
+    /// The referenced task ID.
+    pub target: String,
+    /// 0-based line number of the title or note containing the mention.
+    pub line: usize,
+}
+
+/// Index entries for one log file, valid while the file's mtime and size match.
+#[derive(Debug, Serialize, Deserialize)]
+struct FileIndex {
+    mtime_ns: u64,
+    size: u64,
+    tasks: Vec<IndexEntry>,
+}
+
+/// Full-history map of task ID -> file/line, persisted next to
+/// state.json. Unlike `parse_log` with a scan window, the index covers every
+/// line of every file, so operations by ID work no matter how old the task is.
+/// A file is only re-parsed when its mtime or size changes.
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct TaskIndex {
+    files: BTreeMap<PathBuf, FileIndex>,
+}
+
+impl TaskIndex {
+    /// Load the index and bring it up to date with every configured file.
+    /// The index is a cache: a missing or unreadable index file is rebuilt.
+    pub fn open(config: &Config) -> Result<Self> {
+        let path = Config::index_path();
+        let mut index: Self = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|c| serde_json::from_str(&c).ok())
+            .unwrap_or_default();
+
+        if index.refresh(config, &config.date_fmt()?)? {
+            index.save()?;
+        }
+        Ok(index)
+    }
+
+    fn save(&self) -> Result<()> {
+        let content =
+            serde_json::to_string(&self).map_err(|e| TlError::State(e.to_string()))?;
+        atomic_write(&Config::index_path(), content.as_bytes())
+    }
+
+    /// Re-index files that changed since the last run and drop files that are
+    /// no longer configured. Returns true if anything changed.
+    fn refresh(&mut self, config: &Config, fmt: &DateFormat) -> Result<bool> {
+        let paths = config.all_file_paths();
+        let before = self.files.len();
+        self.files.retain(|p, _| paths.contains(p) && p.exists());
+        let mut changed = self.files.len() != before;
+
+        for path in &paths {
+            let Some((mtime_ns, size)) = file_stamp(path) else {
+                continue;
+            };
+            if let Some(entry) = self.files.get(path) {
+                if entry.mtime_ns == mtime_ns && entry.size == size {
+                    continue;
+                }
+            }
+
+            let content = std::fs::read_to_string(path)?;
+            self.files.insert(
+                path.clone(),
+                FileIndex {
+                    mtime_ns,
+                    size,
+                    tasks: index_content(&content, fmt),
+                },
+            );
+            changed = true;
+        }
+
+        Ok(changed)
+    }
+
+    /// Find the single location of a task ID across all files.
+    pub fn find(&self, id: &str) -> Result<(PathBuf, &IndexEntry)> {
+        let mut found = self
+            .files
+            .iter()
+            .flat_map(|(path, f)| f.tasks.iter().map(move |t| (path, t)))
+            .filter(|(_, t)| t.id == id);
+
+        match (found.next(), found.next()) {
+            (None, _) => Err(TlError::TaskNotFound(id.to_string())),
+            (Some((path, entry)), None) => Ok((path.clone(), entry)),
+            (Some(_), Some(_)) => Err(TlError::DuplicateId(id.to_string())),
+        }
+    }
+
+    /// Every mention of `id` from another task's title or notes, as
+    /// (file, referencing task, link), in file and line order.
+    pub fn references(&self, id: &str) -> Vec<(PathBuf, &IndexEntry, &LinkEntry)> {
+        self.files
+            .iter()
+            .flat_map(|(path, f)| f.tasks.iter().map(move |t| (path, t)))
+            .flat_map(|(path, t)| {
+                t.links
+                    .iter()
+                    .filter(move |l| l.target == id)
+                    .map(move |l| (path.clone(), t, l))
+            })
+            .collect()
+    }
+
+    /// Highest task number ever used for `tag` in any file.
+    pub fn max_number(&self, tag: &str) -> u64 {
+        self.numbers(tag).last().copied().unwrap_or(0)
+    }
+
+    /// Every task number in use for `tag` in any file.
+    pub fn numbers(&self, tag: &str) -> BTreeSet<u64> {
+        let prefix = format!("{}-", tag);
+        self.files
+            .values()
+            .flat_map(|f| &f.tasks)
+            .filter_map(|t| t.id.strip_prefix(&prefix)?.parse::<u64>().ok())
+            .collect()
+    }
+}
+
+/// (mtime in ns since the epoch, size in bytes), or None if the file is missing.
+fn file_stamp(path: &Path) -> Option<(u64, u64)> {
+    let meta = std::fs::metadata(path).ok()?;
+    let mtime = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((mtime.as_nanos() as u64, meta.len()))
+}
+
+/// Parse the whole file (no scan window) and record each task's position.
+fn index_content(content: &str, fmt: &DateFormat) -> Vec<IndexEntry> {
+    parser::parse_log(content, usize::MAX, fmt)
+        .into_iter()
+        .flat_map(|s| s.tasks)
+        .map(|t| IndexEntry {
+            id: t.id(),
+            line: t.line_number,
+            links: task_link_entries(&t),
+        })
+        .collect()
+}
+
+/// Links from a task's title and notes, one entry per line and target.
+/// Mentions of the task itself and alias notes are skipped.
+fn task_link_entries(task: &parser::Task) -> Vec<LinkEntry> {
+    let id = task.id();
+    let notes = task.notes.iter().filter(|n| parser::alias_of(&n.text).is_none());
+    let texts = std::iter::once((task.line_number, &task.title))
+        .chain(notes.map(|n| (n.line_number, &n.text)));
+    let mut links: Vec<LinkEntry> = Vec::new();
+    for (line, text) in texts {
+        for target in parser::extract_links(text) {
+            if target != id && !links.iter().any(|l| l.line == line && l.target == target) {
+                links.push(LinkEntry { target, line });
+            }
+        }
+    }
+    links
+}
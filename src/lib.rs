@@ -1,8 +1,14 @@
 pub mod config;
+pub mod dates;
+pub mod doctor;
 pub mod error;
+pub mod filter;
+pub mod index;
 pub mod lock;
 pub mod mcp;
+pub mod output;
 pub mod parser;
+pub mod recurrence;
 pub mod router;
 pub mod state;
 pub mod tui;
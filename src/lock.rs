@@ -25,6 +25,8 @@ impl FileLock {
 
 impl Drop for FileLock {
     fn drop(&mut self) {
-        let _ = self.file.unlock();
+        // fs2's unlock, to pair with lock_exclusive; std's File::unlock
+        // would shadow it on Rust 1.89+
+        let _ = FileExt::unlock(&self.file);
     }
 }
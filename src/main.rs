@@ -1,8 +1,14 @@
 mod config;
+mod dates;
+mod doctor;
 mod error;
+mod filter;
+mod index;
 mod lock;
 mod mcp;
+mod output;
 mod parser;
+mod recurrence;
 mod router;
 mod state;
 mod tui;
@@ -15,6 +21,10 @@ use clap::{Parser, Subcommand};
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Output format for read commands (search, list, today, deps, refs, file list)
+    #[arg(long, global = true, value_enum, default_value = "text")]
+    format: output::Format,
 }
 
 #[derive(Subcommand)]
@@ -35,6 +45,15 @@ enum Commands {
         /// Mark as high priority
         #[arg(short, long)]
         priority: bool,
+        /// Priority level instead of high: urgent, med, low or none
+        #[arg(long, value_parser = parse_priority)]
+        level: Option<parser::Priority>,
+        /// Add as a subtask of this task (e.g. "dev-4")
+        #[arg(long)]
+        parent: Option<String>,
+        /// Due date (e.g. "2026-11-01")
+        #[arg(long)]
+        due: Option<String>,
     },
 
     /// Mark a task as done: tl done <id>
@@ -43,7 +62,25 @@ enum Commands {
         id: String,
     },
 
-    /// Undo a completed task: tl undo <id>
+    /// Mark a task as in progress: tl start <id>
+    Start {
+        /// Task ID (e.g. "osv-12")
+        id: String,
+    },
+
+    /// Mark a task as blocked: tl block <id>
+    Block {
+        /// Task ID (e.g. "osv-12")
+        id: String,
+    },
+
+    /// Cancel a task without completing it: tl cancel <id>
+    Cancel {
+        /// Task ID (e.g. "osv-12")
+        id: String,
+    },
+
+    /// Undo a completed or cancelled task: tl undo <id>
     Undo {
         /// Task ID (e.g. "osv-12")
         id: String,
@@ -63,6 +100,43 @@ enum Commands {
         query: Vec<String>,
     },
 
+    /// List tasks with filters: tl list --open --tag dev --since 2026-09-01
+    List {
+        /// Only open tasks
+        #[arg(long, conflicts_with = "done")]
+        open: bool,
+        /// Only completed tasks
+        #[arg(long)]
+        done: bool,
+        /// Only tasks in this state
+        #[arg(long, value_parser = parse_status)]
+        status: Option<parser::Status>,
+        /// Only tasks with this tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Only tasks at or above this priority (high if no level is given)
+        #[arg(short, long, num_args = 0..=1, default_missing_value = "high", value_parser = parse_priority)]
+        priority: Option<parser::Priority>,
+        /// Only tasks in the file with this label
+        #[arg(long)]
+        file: Option<String>,
+        /// Only tasks from sections on or after this date
+        #[arg(long)]
+        since: Option<String>,
+        /// Only tasks from sections on or before this date
+        #[arg(long)]
+        until: Option<String>,
+        /// Only open tasks past their due date
+        #[arg(long)]
+        overdue: bool,
+        /// Only tasks with this #label
+        #[arg(long)]
+        label: Option<String>,
+        /// Only tasks assigned to this @person
+        #[arg(long)]
+        assignee: Option<String>,
+    },
+
     /// Show today's section
     Today,
 
@@ -72,12 +146,58 @@ enum Commands {
         old: String,
         /// New tag name
         new: String,
+        /// List the lines that would change without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Set a task's due date: tl due <id> <date|none>
+    Due {
+        /// Task ID (e.g. "osv-12")
+        id: String,
+        /// Due date (e.g. "2026-11-01"), or "none" to clear it
+        date: String,
     },
 
-    /// Toggle priority on a task: tl priority <id>
+    /// Move one task to another tag under a new ID: tl retag <id> <tag>
+    Retag {
+        /// Task ID (e.g. "dev-4")
+        id: String,
+        /// New tag (e.g. "infra")
+        tag: String,
+    },
+
+    /// Move a task to another file or date section: tl move <id> --file <label>
+    Move {
+        /// Task ID (e.g. "osv-12")
+        id: String,
+        /// Label of the destination file (default: the task's current file)
+        #[arg(long)]
+        file: Option<String>,
+        /// Date of the destination section, e.g. "today" (default: the task's current section)
+        #[arg(long)]
+        date: Option<String>,
+    },
+
+    /// Show what a task is waiting on: tl deps <id>
+    Deps {
+        /// Task ID (e.g. "osv-12")
+        id: String,
+    },
+
+    /// Show which tasks mention a task: tl refs <id>
+    Refs {
+        /// Task ID (e.g. "osv-12")
+        id: String,
+    },
+
+    /// Set or toggle priority on a task: tl priority <id> [urgent|high|med|low|none]
     Priority {
         /// Task ID (e.g. "osv-12")
         id: String,
+        /// Priority level; toggles between high and none if omitted
+        #[arg(value_parser = parse_priority)]
+        level: Option<parser::Priority>,
     },
 
     /// Edit a task's title: tl edit <id> <new title>
@@ -94,6 +214,13 @@ enum Commands {
         id: String,
     },
 
+    /// Check the logs and state for problems: tl doctor [--fix]
+    Doctor {
+        /// Apply every available fix without asking
+        #[arg(long)]
+        fix: bool,
+    },
+
     /// Open interactive TUI
     Tui,
 
@@ -145,16 +272,63 @@ fn main() {
             tag,
             title,
             priority,
-        } => cmd_add(&tag, &title.join(" "), priority),
+            level,
+            parent,
+            due,
+        } => cmd_add(
+            &tag,
+            &title.join(" "),
+            level.unwrap_or(if priority { parser::Priority::High } else { parser::Priority::None }),
+            parent.as_deref(),
+            due.as_deref(),
+        ),
         Commands::Done { id } => cmd_done(&id),
+        Commands::Start { id } => cmd_set_status(&id, parser::Status::InProgress),
+        Commands::Block { id } => cmd_set_status(&id, parser::Status::Blocked),
+        Commands::Cancel { id } => cmd_set_status(&id, parser::Status::Cancelled),
         Commands::Undo { id } => cmd_undo(&id),
         Commands::Note { id, text } => cmd_note(&id, &text.join(" ")),
-        Commands::Search { query } => cmd_search(&query.join(" ")),
-        Commands::Today => cmd_today(),
-        Commands::Rename { old, new } => cmd_rename(&old, &new),
-        Commands::Priority { id } => cmd_priority(&id),
+        Commands::Search { query } => cmd_search(&query.join(" "), cli.format),
+        Commands::List {
+            open,
+            done,
+            status,
+            tag,
+            priority,
+            file,
+            since,
+            until,
+            overdue,
+            label,
+            assignee,
+        } => cmd_list(
+            filter::TaskFilter {
+                open,
+                done,
+                status,
+                tag,
+                priority,
+                file,
+                overdue,
+                label,
+                assignee,
+                ..Default::default()
+            },
+            since.as_deref(),
+            until.as_deref(),
+            cli.format,
+        ),
+        Commands::Today => cmd_today(cli.format),
+        Commands::Rename { old, new, dry_run } => cmd_rename(&old, &new, dry_run),
+        Commands::Due { id, date } => cmd_due(&id, &date),
+        Commands::Retag { id, tag } => cmd_retag(&id, &tag),
+        Commands::Move { id, file, date } => cmd_move(&id, file.as_deref(), date.as_deref()),
+        Commands::Deps { id } => cmd_deps(&id, cli.format),
+        Commands::Refs { id } => cmd_refs(&id, cli.format),
+        Commands::Priority { id, level } => cmd_priority(&id, level),
         Commands::Edit { id, title } => cmd_edit(&id, &title.join(" ")),
         Commands::Delete { id } => cmd_delete(&id),
+        Commands::Doctor { fix } => cmd_doctor(fix),
         Commands::Tui => cmd_tui(),
         Commands::Mcp => cmd_mcp(),
         Commands::File { action } => match action {
@@ -165,7 +339,7 @@ fn main() {
                 tags,
                 insert,
             } => cmd_file_add(&path, &label, &mode, &tags, &insert),
-            FileAction::List => cmd_file_list(),
+            FileAction::List => cmd_file_list(cli.format),
             FileAction::Remove { label } => cmd_file_remove(&label),
         },
     };
@@ -184,18 +358,139 @@ fn cmd_init(log_path: Option<&str>) -> error::Result<()> {
     Ok(())
 }
 
-fn cmd_add(tag: &str, title: &str, priority: bool) -> error::Result<()> {
+fn cmd_add(
+    tag: &str,
+    title: &str,
+    priority: parser::Priority,
+    parent: Option<&str>,
+    due: Option<&str>,
+) -> error::Result<()> {
     if title.is_empty() {
         return Err(error::TlError::Other("title cannot be empty".to_string()));
     }
-    let id = writer::add_task_with_priority(tag, title, priority)?;
+    let title = match due {
+        Some(due) => parser::with_due(title, Some(config::Config::load()?.parse_date_arg(due)?)),
+        None => title.to_string(),
+    };
+    let id = match parent {
+        Some(parent) => writer::add_subtask(parent, tag, &title, priority)?,
+        None => writer::add_task_with_priority(tag, &title, priority)?,
+    };
     println!("created {}", id);
     Ok(())
 }
 
 fn cmd_done(id: &str) -> error::Result<()> {
-    writer::complete_task(id)?;
+    let completion = writer::complete_task(id)?;
+    if !completion.open_blockers.is_empty() {
+        eprintln!(
+            "warning: {} is still blocked by {}",
+            id,
+            completion.open_blockers.join(", ")
+        );
+    }
     println!("completed {}", id);
+    if let Some((next, date)) = completion.recurred {
+        let fmt = config::Config::load()?.date_fmt()?;
+        println!("recurs as {} on {}", next, fmt.format(date));
+    }
+    Ok(())
+}
+
+fn cmd_set_status(id: &str, status: parser::Status) -> error::Result<()> {
+    writer::set_status(id, status)?;
+    println!("{} is now {}", id, status.name());
+    Ok(())
+}
+
+fn cmd_due(id: &str, date: &str) -> error::Result<()> {
+    if date == "none" {
+        writer::set_due(id, None)?;
+        println!("cleared due date on {}", id);
+    } else {
+        let due = config::Config::load()?.parse_date_arg(date)?;
+        writer::set_due(id, Some(due))?;
+        println!("{} due {}", id, due.format("%Y-%m-%d"));
+    }
+    Ok(())
+}
+
+fn cmd_retag(id: &str, tag: &str) -> error::Result<()> {
+    let new_id = writer::retag_task(id, tag)?;
+    println!("retagged {} -> {}", id, new_id);
+    Ok(())
+}
+
+fn cmd_move(id: &str, file: Option<&str>, date: Option<&str>) -> error::Result<()> {
+    if file.is_none() && date.is_none() {
+        return Err(error::TlError::Other(
+            "nothing to do: give --file and/or --date".to_string(),
+        ));
+    }
+    let cfg = config::Config::load()?;
+    let date = date.map(|d| cfg.parse_date_arg(d)).transpose()?;
+    let (label, date) = writer::move_task(id, file, date)?;
+    println!("moved {} to {} under {}", id, label, cfg.date_fmt()?.format(date));
+    Ok(())
+}
+
+fn cmd_deps(id: &str, format: output::Format) -> error::Result<()> {
+    let pool = writer::dependency_pool(id)?;
+    let refs: Vec<&parser::Task> = pool.iter().map(|(_, t)| t).collect();
+    let Some(root) = refs.iter().copied().find(|t| t.id() == id) else {
+        return Err(error::TlError::TaskNotFound(id.to_string()));
+    };
+    let tree = parser::blocker_tree(&refs, root);
+    let dependents = parser::dependents(&refs, root);
+
+    if format != output::Format::Text {
+        let file = |t: &parser::Task| {
+            pool.iter()
+                .find(|(_, p)| p.id() == t.id())
+                .map_or(String::new(), |(label, _)| label.clone())
+        };
+        let mut records = vec![output::DepRecord::new("root", 0, &file(root), root)];
+        for (depth, t) in &tree {
+            records.push(output::DepRecord::new("blocker", *depth, &file(t), t));
+        }
+        for t in &dependents {
+            records.push(output::DepRecord::new("dependent", 1, &file(t), t));
+        }
+        return print_records(&records, format);
+    }
+
+    let line = |t: &parser::Task| {
+        format!("[{}] {}{} {}", t.status.marker(), t.id(), t.written_priority(), t.title)
+    };
+    println!("{}", line(root));
+    if tree.is_empty() {
+        println!("  no blockers");
+    }
+    for (depth, task) in &tree {
+        println!("{}└─ {}", "   ".repeat(depth - 1), line(task));
+    }
+    if !dependents.is_empty() {
+        let ids: Vec<String> = dependents.iter().map(|t| t.id()).collect();
+        println!("blocks: {}", ids.join(", "));
+    }
+    Ok(())
+}
+
+fn cmd_refs(id: &str, format: output::Format) -> error::Result<()> {
+    let refs = writer::task_references(id)?;
+    if format != output::Format::Text {
+        let records: Vec<output::ReferenceRecord> = refs.iter().map(Into::into).collect();
+        return print_records(&records, format);
+    }
+    if refs.is_empty() {
+        println!("no references to {}", id);
+        return Ok(());
+    }
+    let id_width = refs.iter().map(|r| r.from.len()).max().unwrap_or(0);
+    for r in &refs {
+        let kind = if r.in_note { "note " } else { "title" };
+        println!("{:<w$}  {}  {}:{}  {}", r.from, kind, r.file, r.line + 1, r.text, w = id_width);
+    }
     Ok(())
 }
 
@@ -209,53 +504,176 @@ fn cmd_note(id: &str, text: &str) -> error::Result<()> {
     if text.is_empty() {
         return Err(error::TlError::Other("note text cannot be empty".to_string()));
     }
-    writer::add_note(id, text)?;
+    writer::add_note(id, text, Some("cli"))?;
     println!("noted on {}", id);
     Ok(())
 }
 
-fn cmd_search(query: &str) -> error::Result<()> {
+/// Print records in a machine-readable format.
+fn print_records<T: serde::Serialize + output::TsvRow>(
+    records: &[T],
+    format: output::Format,
+) -> error::Result<()> {
+    let text = output::render(records, format)?;
+    if !text.is_empty() {
+        println!("{}", text);
+    }
+    Ok(())
+}
+
+/// Print tasks (with their file labels) as machine-readable task records.
+fn print_task_records(tasks: &[(String, parser::Task)], format: output::Format) -> error::Result<()> {
+    let records: Vec<output::TaskRecord> = tasks
+        .iter()
+        .map(|(label, task)| output::TaskRecord::new(label, task))
+        .collect();
+    print_records(&records, format)
+}
+
+fn cmd_search(query: &str, format: output::Format) -> error::Result<()> {
     if query.is_empty() {
         return Err(error::TlError::Other("search query cannot be empty".to_string()));
     }
-    let tasks = writer::search(query)?;
+    let mut tasks = writer::search_with_files(query)?;
+    tasks.sort_by_key(|(_, t)| t.section_date);
+    if format != output::Format::Text {
+        return print_task_records(&tasks, format);
+    }
     if tasks.is_empty() {
         println!("no tasks found matching \"{}\"", query);
         return Ok(());
     }
-    for task in &tasks {
-        let status = if task.done { "x" } else { " " };
-        let priority = if task.priority { "!" } else { "" };
-        println!("[{}] {}{} {}", status, task.id(), priority, task.title);
+    let fmt = config::Config::load()?.date_fmt()?;
+    for (_, task) in &tasks {
+        let status = task.status.marker();
+        println!("[{}] {}{} {}", status, task.id(), task.written_priority(), task.title);
         for note in &task.notes {
-            println!("      - {}", note.text);
+            println!("      - {}", note.display(&fmt));
         }
     }
     Ok(())
 }
 
-fn cmd_today() -> error::Result<()> {
+fn cmd_list(
+    mut filter: filter::TaskFilter,
+    since: Option<&str>,
+    until: Option<&str>,
+    format: output::Format,
+) -> error::Result<()> {
+    let cfg = config::Config::load()?;
+    filter.since = since.map(|s| cfg.parse_date_arg(s)).transpose()?;
+    filter.until = until.map(|s| cfg.parse_date_arg(s)).transpose()?;
+
+    let all = writer::all_tasks_with_files()?;
+    let mut tasks: Vec<(String, parser::Task)> = all
+        .iter()
+        .filter(|(label, task)| filter.matches(label, task))
+        .cloned()
+        .collect();
+    tasks.sort_by_key(|(_, t)| t.section_date);
+    if format != output::Format::Text {
+        return print_task_records(&tasks, format);
+    }
+
+    if tasks.is_empty() {
+        println!("no tasks found");
+        return Ok(());
+    }
+
+    let multi = cfg.effective_files().len() > 1;
+    let today = dates::today();
+    let id_width = tasks
+        .iter()
+        .map(|(_, t)| t.id().len() + t.written_priority().len())
+        .max()
+        .unwrap_or(0);
+    let label_width = tasks.iter().map(|(l, _)| l.len()).max().unwrap_or(0);
+
+    for (label, task) in &tasks {
+        let status = task.status.marker();
+        let id = format!("{}{}", task.id(), task.written_priority());
+        let age = task
+            .section_date
+            .map(|d| format!("{}d", (today - d).num_days()))
+            .unwrap_or_else(|| "-".to_string());
+        let file_col = if multi {
+            format!("{:<width$}  ", label, width = label_width)
+        } else {
+            String::new()
+        };
+        let subtask = if task.parent.is_some() { "└ " } else { "" };
+        let rollup = parser::subtask_progress(all.iter().map(|(_, t)| t), &task.id())
+            .map(|(done, total)| format!("  ({}/{} subtasks)", done, total))
+            .unwrap_or_default();
+        println!(
+            "[{}] {:<id_width$}  {:>5}  {}{}{}{}",
+            status,
+            id,
+            age,
+            file_col,
+            subtask,
+            task.title,
+            rollup,
+            id_width = id_width
+        );
+    }
+    Ok(())
+}
+
+fn cmd_today(format: output::Format) -> error::Result<()> {
+    if format != output::Format::Text {
+        let today = dates::today();
+        let tasks: Vec<(String, parser::Task)> = writer::all_tasks_with_files()?
+            .into_iter()
+            .filter(|(_, t)| t.section_date == Some(today))
+            .collect();
+        return print_task_records(&tasks, format);
+    }
     let text = writer::get_today()?;
     println!("{}", text);
     Ok(())
 }
 
-fn cmd_rename(old: &str, new: &str) -> error::Result<()> {
-    writer::rename_tag(old, new)?;
-    println!("renamed {} -> {}", old, new);
+fn cmd_rename(old: &str, new: &str, dry_run: bool) -> error::Result<()> {
+    let changes = writer::rename_tag(old, new, dry_run)?;
+    if !dry_run {
+        println!("renamed {} -> {} ({} lines updated)", old, new, changes.len());
+        return Ok(());
+    }
+    for change in &changes {
+        println!("{}:{}", change.file, change.line + 1);
+        println!("  - {}", change.before.trim());
+        println!("  + {}", change.after.trim());
+    }
+    println!("{} lines would change", changes.len());
     Ok(())
 }
 
-fn cmd_priority(id: &str) -> error::Result<()> {
-    let new_priority = writer::toggle_priority(id)?;
-    if new_priority {
-        println!("{} marked as high priority", id);
-    } else {
-        println!("{} marked as normal priority", id);
+fn cmd_priority(id: &str, level: Option<parser::Priority>) -> error::Result<()> {
+    let new_priority = match level {
+        Some(level) => {
+            writer::set_priority(id, level)?;
+            level
+        }
+        None => writer::toggle_priority(id)?,
+    };
+    match new_priority {
+        parser::Priority::None => println!("{} marked as normal priority", id),
+        p => println!("{} marked as {} priority", id, p.name()),
     }
     Ok(())
 }
 
+fn parse_status(s: &str) -> Result<parser::Status, String> {
+    parser::Status::from_name(s).ok_or_else(|| {
+        format!("'{}' is not one of open, in-progress, blocked, done, cancelled", s)
+    })
+}
+
+fn parse_priority(s: &str) -> Result<parser::Priority, String> {
+    parser::Priority::from_name(s).ok_or_else(|| format!("'{}' is not one of urgent, high, med, low, none", s))
+}
+
 fn cmd_edit(id: &str, new_title: &str) -> error::Result<()> {
     if new_title.is_empty() {
         return Err(error::TlError::Other("title cannot be empty".to_string()));
@@ -271,6 +689,49 @@ fn cmd_delete(id: &str) -> error::Result<()> {
     Ok(())
 }
 
+fn cmd_doctor(fix: bool) -> error::Result<()> {
+    use std::io::{IsTerminal, Write};
+
+    let issues = doctor::check()?;
+    if issues.is_empty() {
+        println!("no problems found");
+        return Ok(());
+    }
+
+    // Without --fix, ask about each fixable issue when run from a terminal
+    let interactive = !fix && std::io::stdin().is_terminal();
+    let mut remaining = 0;
+    for issue in &issues {
+        println!("{}", issue);
+        let apply = issue.is_fixable()
+            && (fix || interactive && {
+                print!("  fix? [y/N] ");
+                std::io::stdout().flush()?;
+                let mut answer = String::new();
+                std::io::stdin().read_line(&mut answer)?;
+                answer.trim().eq_ignore_ascii_case("y")
+            });
+        if !apply {
+            if issue.is_problem() {
+                remaining += 1;
+            }
+            continue;
+        }
+        for change in doctor::fix(issue)? {
+            println!("  {}", change);
+        }
+    }
+
+    if remaining == 0 {
+        return Ok(());
+    }
+    if !fix && !interactive && issues.iter().any(|i| i.is_fixable()) {
+        println!("run `tl doctor --fix` to repair what can be fixed automatically");
+    }
+    let noun = if remaining == 1 { "problem" } else { "problems" };
+    Err(error::TlError::Other(format!("{} {} left", remaining, noun)))
+}
+
 fn cmd_tui() -> error::Result<()> {
     tui::run()
 }
@@ -345,10 +806,15 @@ fn cmd_file_add(path: &str, label: &str, mode: &str, tags: &[String], insert: &s
     Ok(())
 }
 
-fn cmd_file_list() -> error::Result<()> {
+fn cmd_file_list(format: output::Format) -> error::Result<()> {
     let cfg = config::Config::load()?;
     let files = cfg.effective_files();
 
+    if format != output::Format::Text {
+        let records: Vec<output::FileRecord> = files.iter().map(output::FileRecord::from).collect();
+        return print_records(&records, format);
+    }
+
     if files.len() == 1 && cfg.files.is_empty() {
         println!("single file mode: {}", cfg.log_path);
         println!("(use `tl file add` to enable multi-file)");
@@ -1,13 +1,37 @@
+use crate::config::Config;
+use crate::dates::{self, DateFormat};
 use crate::error::TlError;
+use crate::filter::TaskFilter;
+use crate::output::{FileRecord, ReferenceRecord, TaskRecord};
+use crate::parser::{self, Priority, Status, Task};
 use crate::writer;
 
+use chrono::NaiveDate;
+use rmcp::handler::server::router::prompt::PromptRouter;
 use rmcp::handler::server::router::tool::ToolRouter;
 use rmcp::handler::server::wrapper::Parameters;
-use rmcp::model::{Implementation, ProtocolVersion, ServerCapabilities, ServerInfo};
+use rmcp::model::{
+    AnnotateAble, CallToolResult, GetPromptRequestParams, GetPromptResult, Implementation,
+    ListPromptsResult, ListResourceTemplatesResult, ListResourcesResult, PaginatedRequestParams,
+    PromptMessage, PromptMessageRole, ProtocolVersion, RawResource,
+    RawResourceTemplate, ReadResourceRequestParams, ReadResourceResult, ResourceContents,
+    ResourceUpdatedNotificationParam, ServerCapabilities, ServerInfo, SubscribeRequestParams,
+    UnsubscribeRequestParams,
+};
 use rmcp::schemars;
 use rmcp::schemars::JsonSchema;
-use rmcp::{tool, tool_handler, tool_router, ServerHandler, ServiceExt};
+use rmcp::service::{NotificationContext, Peer, RequestContext};
+use rmcp::{
+    prompt, prompt_handler, prompt_router, tool, tool_handler, tool_router, ErrorData, RoleServer,
+    ServerHandler, ServiceExt,
+};
 use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct CreateTaskParams {
@@ -15,10 +39,19 @@ pub struct CreateTaskParams {
     pub tag: String,
     /// Task title
     pub title: String,
+    /// Priority level: "urgent", "high", "med", "low" or "none" (default)
+    pub priority: Option<String>,
+    /// Label of the log file to add the task to, when the tag could go in more than one (see list_files)
+    pub file: Option<String>,
+    /// Notes to add under the task
+    #[serde(default)]
+    pub notes: Vec<String>,
+    /// Due date, "today" or a date
+    pub due: Option<String>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
-pub struct CompleteTaskParams {
+pub struct TaskIdParams {
     /// Task ID (e.g. "osv-12")
     pub id: String,
 }
@@ -31,17 +64,101 @@ pub struct AddNoteParams {
     pub text: String,
 }
 
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SetPriorityParams {
+    /// Task ID (e.g. "osv-12")
+    pub id: String,
+    /// Priority level: "urgent", "high", "med", "low" or "none" ("p0".."p3" also accepted)
+    pub priority: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct EditTaskParams {
+    /// Task ID (e.g. "osv-12")
+    pub id: String,
+    /// New title
+    pub title: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DeleteNoteParams {
+    /// Task ID (e.g. "osv-12")
+    pub id: String,
+    /// 0-based position of the note under the task, as listed by get_task
+    pub index: usize,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RenameTagParams {
+    /// Current tag name
+    pub old: String,
+    /// New tag name
+    pub new: String,
+    /// Only list the lines that would change
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SetDueParams {
+    /// Task ID (e.g. "osv-12")
+    pub id: String,
+    /// Due date ("YYYY-MM-DD", the configured date format, or "today"); omit to clear it
+    pub due: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct MoveTaskParams {
+    /// Task ID (e.g. "osv-12")
+    pub id: String,
+    /// Label of the destination file (default: the task's current file)
+    pub file: Option<String>,
+    /// Date of the destination section ("YYYY-MM-DD" or "today"; default: the task's current section)
+    pub date: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RetagTaskParams {
+    /// Task ID (e.g. "dev-4")
+    pub id: String,
+    /// New tag (e.g. "infra")
+    pub tag: String,
+}
+
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct SearchParams {
     /// Search query
     pub query: String,
     /// Optional tag filter
     pub tag: Option<String>,
+    /// Optional label filter (e.g. "security" for #security)
+    pub label: Option<String>,
+    /// Optional assignee filter (e.g. "alice" for @alice)
+    pub assignee: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct StandupParams {
+    /// Day to report on, "today" or a date (defaults to yesterday)
+    pub date: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TriageParams {
+    /// Tag whose open tasks to triage (e.g. "dev")
+    pub tag: String,
 }
 
 #[derive(Clone)]
 pub struct TlMcpServer {
     tool_router: ToolRouter<Self>,
+    prompt_router: PromptRouter<Self>,
+    subscriptions: Subscriptions,
+}
+
+impl Default for TlMcpServer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[tool_router]
@@ -49,91 +166,682 @@ impl TlMcpServer {
     pub fn new() -> Self {
         Self {
             tool_router: Self::tool_router(),
+            prompt_router: Self::prompt_router(),
+            subscriptions: Subscriptions::default(),
         }
     }
 
     /// Initialize the task log environment. Creates config, log, and state files if missing.
     #[tool(description = "Initialize the task log environment. Creates config, log, and state files if missing.")]
-    fn init_log(&self) -> String {
-        match writer::init(None) {
-            Ok(()) => "Task log initialized successfully.".to_string(),
-            Err(e) => format!("Error: {}", e),
-        }
+    fn init_log(&self) -> ToolResult {
+        respond(|| {
+            writer::init(None)?;
+            Ok(json!({ "initialized": true }))
+        })
     }
 
-    /// Create a new task with a tag and title. Returns the assigned task ID.
-    #[tool(description = "Create a new task with a tag and title. Returns the assigned task ID.")]
-    fn create_task(&self, Parameters(params): Parameters<CreateTaskParams>) -> String {
-        match writer::add_task(&params.tag, &params.title) {
-            Ok(id) => format!("Created task: {}", id),
-            Err(e) => format!("Error: {}", e),
-        }
+    /// Create a new task with a tag and title, and optionally its priority,
+    /// file, due date and first notes. Returns the new task.
+    #[tool(description = "Create a new task with a tag and title, optionally with a priority, target file label, due date and initial notes, all written in one step. Returns the new task, including its assigned ID.")]
+    fn create_task(&self, Parameters(params): Parameters<CreateTaskParams>) -> ToolResult {
+        respond(|| {
+            let priority = match params.priority.as_deref() {
+                Some(name) => parse_priority(name)?,
+                None => Priority::None,
+            };
+            let title = match params.due.as_deref() {
+                Some(due) => parser::with_due(&params.title, Some(Config::load()?.parse_date_arg(due)?)),
+                None => params.title.clone(),
+            };
+            let id = writer::add_task_with_notes(
+                &params.tag,
+                &title,
+                priority,
+                params.file.as_deref(),
+                &params.notes,
+                Some("agent"),
+            )?;
+            task_json(&id)
+        })
     }
 
     /// Mark a task as completed by its ID (e.g. 'osv-12').
-    #[tool(description = "Mark a task as completed by its ID (e.g. 'osv-12').")]
-    fn complete_task(&self, Parameters(params): Parameters<CompleteTaskParams>) -> String {
-        match writer::complete_task(&params.id) {
-            Ok(()) => format!("Completed task: {}", params.id),
-            Err(e) => format!("Error: {}", e),
-        }
+    #[tool(description = "Mark a task as completed by its ID (e.g. 'osv-12'). Reports the next occurrence of a recurring task and any blockers still open.")]
+    fn complete_task(&self, Parameters(params): Parameters<TaskIdParams>) -> ToolResult {
+        respond(|| {
+            let completion = writer::complete_task(&params.id)?;
+            let mut value = task_json(&params.id)?;
+            value["recurred"] = match completion.recurred {
+                Some((id, date)) => json!({ "id": id, "date": date.format("%Y-%m-%d").to_string() }),
+                None => Value::Null,
+            };
+            value["open_blockers"] = json!(completion.open_blockers);
+            Ok(value)
+        })
+    }
+
+    /// Mark a task as in progress by its ID.
+    #[tool(description = "Mark a task as in progress by its ID (e.g. 'osv-12').")]
+    fn start_task(&self, Parameters(params): Parameters<TaskIdParams>) -> ToolResult {
+        set_status(&params.id, Status::InProgress)
+    }
+
+    /// Mark a task as blocked by its ID.
+    #[tool(description = "Mark a task as blocked by its ID (e.g. 'osv-12').")]
+    fn block_task(&self, Parameters(params): Parameters<TaskIdParams>) -> ToolResult {
+        set_status(&params.id, Status::Blocked)
+    }
+
+    /// Cancel a task by its ID without completing it.
+    #[tool(description = "Cancel a task by its ID (e.g. 'osv-12') without completing it.")]
+    fn cancel_task(&self, Parameters(params): Parameters<TaskIdParams>) -> ToolResult {
+        set_status(&params.id, Status::Cancelled)
+    }
+
+    /// Reopen a completed or cancelled task.
+    #[tool(description = "Reopen a completed or cancelled task by its ID. Top-level tasks move to today's section.")]
+    fn undo_task(&self, Parameters(params): Parameters<TaskIdParams>) -> ToolResult {
+        respond(|| {
+            writer::undo_task(&params.id)?;
+            task_json(&params.id)
+        })
+    }
+
+    /// Replace a task's title.
+    #[tool(description = "Replace a task's title by its ID.")]
+    fn edit_task(&self, Parameters(params): Parameters<EditTaskParams>) -> ToolResult {
+        respond(|| {
+            writer::edit_task(&params.id, &params.title)?;
+            task_json(&params.id)
+        })
+    }
+
+    /// Delete a task with its notes and subtasks.
+    #[tool(description = "Delete a task by its ID, along with its notes and subtasks.")]
+    fn delete_task(&self, Parameters(params): Parameters<TaskIdParams>) -> ToolResult {
+        respond(|| {
+            writer::delete_task(&params.id)?;
+            Ok(json!({ "deleted": params.id }))
+        })
     }
 
     /// Add a note to an existing task by its ID.
     #[tool(description = "Add a note to an existing task by its ID.")]
-    fn add_note(&self, Parameters(params): Parameters<AddNoteParams>) -> String {
-        match writer::add_note(&params.id, &params.text) {
-            Ok(()) => format!("Note added to task: {}", params.id),
-            Err(e) => format!("Error: {}", e),
+    fn add_note(&self, Parameters(params): Parameters<AddNoteParams>) -> ToolResult {
+        respond(|| {
+            writer::add_note(&params.id, &params.text, Some("agent"))?;
+            task_json(&params.id)
+        })
+    }
+
+    /// Delete one note from a task.
+    #[tool(description = "Delete one note from a task, by the note's 0-based index in the task's notes.")]
+    fn delete_note(&self, Parameters(params): Parameters<DeleteNoteParams>) -> ToolResult {
+        respond(|| {
+            writer::delete_note(&params.id, params.index)?;
+            task_json(&params.id)
+        })
+    }
+
+    /// Set a task's priority level.
+    #[tool(description = "Set a task's priority level: urgent, high, med, low or none.")]
+    fn set_priority(&self, Parameters(params): Parameters<SetPriorityParams>) -> ToolResult {
+        respond(|| {
+            writer::set_priority(&params.id, parse_priority(&params.priority)?)?;
+            task_json(&params.id)
+        })
+    }
+
+    /// Toggle a task between high and no priority.
+    #[tool(description = "Toggle a task's priority: any level clears it, none makes it high.")]
+    fn toggle_priority(&self, Parameters(params): Parameters<TaskIdParams>) -> ToolResult {
+        respond(|| {
+            writer::toggle_priority(&params.id)?;
+            task_json(&params.id)
+        })
+    }
+
+    /// Set or clear a task's due date.
+    #[tool(description = "Set a task's due date, or clear it by omitting 'due'.")]
+    fn set_due(&self, Parameters(params): Parameters<SetDueParams>) -> ToolResult {
+        respond(|| {
+            let due = params.due.as_deref().map(|d| Config::load()?.parse_date_arg(d)).transpose()?;
+            writer::set_due(&params.id, due)?;
+            task_json(&params.id)
+        })
+    }
+
+    /// Rename a tag across all log files.
+    #[tool(description = "Rename a tag across all log files, rewriting references like old-3 in titles and notes. Set dry_run to preview.")]
+    fn rename_tag(&self, Parameters(params): Parameters<RenameTagParams>) -> ToolResult {
+        respond(|| {
+            let dry_run = params.dry_run.unwrap_or(false);
+            let changes = writer::rename_tag(&params.old, &params.new, dry_run)?;
+            let changes: Vec<Value> = changes
+                .iter()
+                .map(|c| {
+                    json!({
+                        "file": c.file,
+                        "line": c.line + 1,
+                        "before": c.before,
+                        "after": c.after,
+                    })
+                })
+                .collect();
+            Ok(json!({
+                "old": params.old,
+                "new": params.new,
+                "dry_run": dry_run,
+                "changes": changes,
+            }))
+        })
+    }
+
+    /// Move one task to another tag under a new ID.
+    #[tool(description = "Move one task to another tag under a newly allocated ID. Leaves a 'was <old id>' note and rewrites references. Returns the task under its new ID.")]
+    fn retag_task(&self, Parameters(params): Parameters<RetagTaskParams>) -> ToolResult {
+        respond(|| task_json(&writer::retag_task(&params.id, &params.tag)?))
+    }
+
+    /// Move a task with its notes and subtasks to another file or date section.
+    #[tool(description = "Move a task with its notes and subtasks to another log file (by label) and/or date section.")]
+    fn move_task(&self, Parameters(params): Parameters<MoveTaskParams>) -> ToolResult {
+        respond(|| {
+            if params.file.is_none() && params.date.is_none() {
+                return Err(TlError::Other("give a file and/or a date".to_string()));
+            }
+            let date = params.date.as_deref().map(|d| Config::load()?.parse_date_arg(d)).transpose()?;
+            writer::move_task(&params.id, params.file.as_deref(), date)?;
+            task_json(&params.id)
+        })
+    }
+
+    /// Get one task with all its details.
+    #[tool(description = "Get one task by its ID, with status, priority, dates, file and notes, wherever it is in the history.")]
+    fn get_task(&self, Parameters(params): Parameters<TaskIdParams>) -> ToolResult {
+        respond(|| task_json(&params.id))
+    }
+
+    /// List the configured log files.
+    #[tool(description = "List the configured log files with their labels, paths, modes and tags.")]
+    fn list_files(&self) -> ToolResult {
+        respond(|| {
+            let files: Vec<FileRecord> =
+                Config::load()?.effective_files().iter().map(FileRecord::from).collect();
+            Ok(json!({ "files": files }))
+        })
+    }
+
+    /// Search tasks and notes. Optionally filter by tag, #label or @assignee.
+    #[tool(description = "Search tasks and notes. Optionally filter by tag, #label or @assignee.")]
+    fn search_tasks(&self, Parameters(params): Parameters<SearchParams>) -> ToolResult {
+        respond(|| {
+            let filter = TaskFilter {
+                tag: params.tag,
+                label: params.label,
+                assignee: params.assignee,
+                ..Default::default()
+            };
+            let tasks: Vec<TaskRecord> = writer::search_with_files(&params.query)?
+                .iter()
+                .filter(|(label, t)| filter.matches(label, t))
+                .map(|(label, t)| TaskRecord::new(label, t))
+                .collect();
+            Ok(json!({ "tasks": tasks }))
+        })
+    }
+
+    /// List the tasks whose title or notes mention a task (backlinks).
+    #[tool(description = "List the tasks whose title or notes mention the given task ID (backlinks)")]
+    fn get_task_references(&self, Parameters(params): Parameters<TaskIdParams>) -> ToolResult {
+        respond(|| {
+            let refs: Vec<ReferenceRecord> = writer::task_references(&params.id)?
+                .iter()
+                .map(ReferenceRecord::from)
+                .collect();
+            Ok(json!({ "id": params.id, "references": refs }))
+        })
+    }
+
+    /// Get the raw text of today's section from the log.
+    #[tool(description = "Get the raw text of today's section from the log.")]
+    fn get_today_section(&self) -> ToolResult {
+        respond(|| {
+            Ok(json!({
+                "date": dates::today().format("%Y-%m-%d").to_string(),
+                "text": writer::get_today()?,
+            }))
+        })
+    }
+}
+
+#[prompt_router]
+impl TlMcpServer {
+    /// Summarise a day's work for a standup.
+    #[prompt(name = "standup", description = "Draft a standup update: tasks completed and notes written on a day (yesterday by default), plus what's in progress or blocked now.")]
+    fn standup(&self, Parameters(params): Parameters<StandupParams>) -> PromptResult {
+        let date = match params.date.as_deref() {
+            Some(s) => Config::load().and_then(|c| c.parse_date_arg(s)).map_err(prompt_error)?,
+            None => dates::today() - chrono::Days::new(1),
+        };
+        prompt(
+            "Standup update",
+            standup_text(date).map_err(prompt_error)?,
+        )
+    }
+
+    /// Review the open tasks of one tag.
+    #[prompt(name = "triage", description = "Triage the open tasks of a tag: suggest priorities and flag stale, duplicate or cancellable tasks.")]
+    fn triage(&self, Parameters(params): Parameters<TriageParams>) -> PromptResult {
+        match triage_text(&params.tag).map_err(prompt_error)? {
+            Some(text) => prompt(&format!("Triage of open {} tasks", params.tag), text),
+            None => Err(ErrorData::invalid_params(
+                format!("no open tasks tagged '{}'", params.tag),
+                None,
+            )),
+        }
+    }
+
+    /// Plan today from open priorities.
+    #[prompt(name = "plan_day", description = "Plan today from open tasks: overdue, due or scheduled, in progress and high priority first, with today's section of the log.")]
+    fn plan_day(&self) -> PromptResult {
+        prompt("Plan for today", plan_day_text().map_err(prompt_error)?)
+    }
+}
+
+type PromptResult = std::result::Result<GetPromptResult, ErrorData>;
+
+fn prompt(description: &str, text: String) -> PromptResult {
+    Ok(GetPromptResult {
+        description: Some(description.to_string()),
+        messages: vec![PromptMessage::new_text(PromptMessageRole::User, text)],
+    })
+}
+
+fn prompt_error(e: TlError) -> ErrorData {
+    let data = Some(json!({ "error": e.code() }));
+    match e {
+        TlError::Parse(_) => ErrorData::invalid_params(e.to_string(), data),
+        _ => ErrorData::internal_error(e.to_string(), data),
+    }
+}
+
+/// A task as its log line, followed by the file and section it's in and any
+/// open blockers, with its notes underneath.
+fn describe_task(
+    label: &str,
+    task: &Task,
+    all: &[&Task],
+    fmt: &DateFormat,
+    with_notes: bool,
+) -> String {
+    let mut out = format!("{} ({}, {})", task.render(fmt).trim_start(), label, task.date);
+    let waiting: Vec<String> = parser::open_blockers(all, task).iter().map(|t| t.id()).collect();
+    if !waiting.is_empty() {
+        out.push_str(&format!(" -- waiting on {}", waiting.join(", ")));
+    }
+    if with_notes {
+        for note in &task.notes {
+            out.push_str(&format!("\n  - {}", note.display(fmt)));
+        }
+    }
+    out
+}
+
+/// A `## heading` followed by one entry per line, or "(none)".
+fn prompt_section(heading: &str, entries: &[String]) -> String {
+    let body = if entries.is_empty() {
+        "(none)".to_string()
+    } else {
+        entries.join("\n")
+    };
+    format!("## {}\n{}", heading, body)
+}
+
+fn standup_text(date: NaiveDate) -> crate::error::Result<String> {
+    let fmt = Config::load()?.date_fmt()?;
+    let tasks = writer::all_tasks_with_files()?;
+    let all: Vec<&Task> = tasks.iter().map(|(_, t)| t).collect();
+    let describe = |label: &str, task: &Task, with_notes| {
+        describe_task(label, task, &all, &fmt, with_notes)
+    };
+
+    let completed: Vec<String> = tasks
+        .iter()
+        .filter(|(_, t)| t.status == Status::Done)
+        .filter(|(_, t)| t.completed_at.is_some_and(|at| at.date() == date))
+        .map(|(label, t)| describe(label, t, false))
+        .collect();
+    let noted: Vec<String> = tasks
+        .iter()
+        .filter_map(|(label, t)| {
+            let notes: Vec<String> = t
+                .notes
+                .iter()
+                .filter(|n| n.created_at.is_some_and(|at| at.date() == date))
+                .map(|n| format!("\n  - {}", n.display(&fmt)))
+                .collect();
+            if notes.is_empty() {
+                return None;
+            }
+            Some(format!("{}{}", describe(label, t, false), notes.concat()))
+        })
+        .collect();
+    let with_status = |status: Status| -> Vec<String> {
+        tasks
+            .iter()
+            .filter(|(_, t)| t.status == status)
+            .map(|(label, t)| describe(label, t, true))
+            .collect()
+    };
+
+    let day = fmt.format(date);
+    Ok([
+        format!(
+            "Write a short standup update from my task log. Cover what I got done on {}, what I'm working on now, and anything blocking me. Refer to tasks by ID.",
+            day
+        ),
+        prompt_section(&format!("Completed on {}", day), &completed),
+        prompt_section(&format!("Notes written on {}", day), &noted),
+        prompt_section("In progress", &with_status(Status::InProgress)),
+        prompt_section("Blocked", &with_status(Status::Blocked)),
+    ]
+    .join("\n\n"))
+}
+
+/// `None` if the tag has no open tasks.
+fn triage_text(tag: &str) -> crate::error::Result<Option<String>> {
+    let fmt = Config::load()?.date_fmt()?;
+    let tasks = writer::all_tasks_with_files()?;
+    let all: Vec<&Task> = tasks.iter().map(|(_, t)| t).collect();
+
+    let mut open: Vec<&(String, Task)> = tasks
+        .iter()
+        .filter(|(_, t)| t.tag == tag && !t.status.is_closed())
+        .collect();
+    if open.is_empty() {
+        return Ok(None);
+    }
+    // Oldest first, so stale tasks are easy to spot
+    open.sort_by_key(|(_, t)| (t.section_date, t.number));
+    let entries: Vec<String> = open
+        .iter()
+        .map(|(label, t)| describe_task(label, t, &all, &fmt, true))
+        .collect();
+
+    Ok(Some([
+        format!(
+            "Triage my open {} tasks below, oldest first. Suggest a priority for each (urgent, high, med, low or none) and flag anything that looks stale, duplicated, blocked for too long or ready to cancel. Today is {}. Ask me before changing anything, then use set_priority, cancel_task or add_note.",
+            tag,
+            fmt.format(dates::today())
+        ),
+        prompt_section(&format!("Open {} tasks ({})", tag, entries.len()), &entries),
+    ]
+    .join("\n\n")))
+}
+
+const PLAN_GROUPS: [&str; 5] = [
+    "Overdue",
+    "Due today",
+    "In progress",
+    "Scheduled for today or earlier",
+    "Urgent or high priority",
+];
+
+/// Index into `PLAN_GROUPS` of the first group an open task falls in.
+fn plan_group(task: &Task, today: NaiveDate) -> Option<usize> {
+    if task.is_overdue(today) {
+        Some(0)
+    } else if task.due == Some(today) {
+        Some(1)
+    } else if task.status == Status::InProgress {
+        Some(2)
+    } else if task.scheduled.is_some_and(|d| d <= today) {
+        Some(3)
+    } else if task.priority >= Priority::High {
+        Some(4)
+    } else {
+        None
+    }
+}
+
+fn plan_day_text() -> crate::error::Result<String> {
+    let fmt = Config::load()?.date_fmt()?;
+    let today = dates::today();
+    let tasks = writer::all_tasks_with_files()?;
+    let all: Vec<&Task> = tasks.iter().map(|(_, t)| t).collect();
+
+    let mut grouped: Vec<Vec<String>> = vec![Vec::new(); PLAN_GROUPS.len()];
+    let mut rest = Vec::new();
+    for (label, task) in tasks.iter().filter(|(_, t)| !t.status.is_closed()) {
+        match plan_group(task, today) {
+            Some(i) => grouped[i].push(describe_task(label, task, &all, &fmt, true)),
+            None => rest.push(describe_task(label, task, &all, &fmt, false)),
+        }
+    }
+
+    let mut parts = vec![format!(
+        "Plan my day ({}). From the open tasks below, pick a realistic set to work on today, in order, and say briefly why. Overdue and due items come first unless something blocks them; tasks waiting on open blockers can't start yet. Today's section of the log so far is at the end.",
+        fmt.format(today)
+    )];
+    for (heading, entries) in PLAN_GROUPS.iter().zip(&grouped) {
+        parts.push(prompt_section(heading, entries));
+    }
+    parts.push(prompt_section("Other open tasks", &rest));
+    let today_text = writer::get_today().unwrap_or_else(|_| "(no section yet)".to_string());
+    parts.push(format!("## Today's section\n{}", today_text));
+    Ok(parts.join("\n\n"))
+}
+
+type ToolResult = std::result::Result<CallToolResult, ErrorData>;
+
+/// Run a tool body and wrap its outcome as structured content. Failures set
+/// `is_error` and carry the `TlError` variant name as `error`, e.g.
+/// `{"error": "TaskNotFound", "message": "Task not found: dev-9"}`.
+fn respond(body: impl FnOnce() -> crate::error::Result<Value>) -> ToolResult {
+    Ok(match body() {
+        Ok(value) => CallToolResult::structured(value),
+        Err(e) => CallToolResult::structured_error(json!({
+            "error": e.code(),
+            "message": e.to_string(),
+        })),
+    })
+}
+
+/// `{"task": <task record>}` for a task as it is on disk now.
+fn task_json(id: &str) -> crate::error::Result<Value> {
+    let (label, task) = writer::get_task(id)?;
+    Ok(json!({ "task": TaskRecord::new(&label, &task) }))
+}
+
+fn parse_priority(name: &str) -> crate::error::Result<Priority> {
+    Priority::from_name(name).ok_or_else(|| {
+        TlError::Parse(format!(
+            "invalid priority '{}' (use urgent, high, med, low or none)",
+            name
+        ))
+    })
+}
+
+fn set_status(id: &str, status: Status) -> ToolResult {
+    respond(|| {
+        writer::set_status(id, status)?;
+        task_json(id)
+    })
+}
+
+/// How often the log files are checked for changes while a client has
+/// resource subscriptions.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Subscribed resource URIs, each with the content last seen for it (`None`
+/// if it couldn't be read).
+type Subscriptions = Arc<Mutex<HashMap<String, Option<String>>>>;
+
+/// Resources the server publishes:
+/// - `tl://task/<id>` -- one task as JSON, same shape as the tools' task objects
+/// - `tl://file/<label>` -- the raw markdown of a configured log file
+/// - `tl://today` -- today's section from every file
+enum Resource {
+    Task(String),
+    File(String),
+    Today,
+}
+
+impl Resource {
+    fn parse(uri: &str) -> Option<Self> {
+        let rest = uri.strip_prefix("tl://")?;
+        if rest == "today" {
+            return Some(Resource::Today);
         }
+        if let Some(id) = rest.strip_prefix("task/").filter(|id| !id.is_empty()) {
+            return Some(Resource::Task(id.to_string()));
+        }
+        rest.strip_prefix("file/")
+            .filter(|label| !label.is_empty())
+            .map(|label| Resource::File(label.to_string()))
     }
 
-    /// Search tasks and notes. Optionally filter by tag.
-    #[tool(description = "Search tasks and notes. Optionally filter by tag.")]
-    fn search_tasks(&self, Parameters(params): Parameters<SearchParams>) -> String {
-        match writer::search(&params.query) {
-            Ok(tasks) => {
-                let filtered: Vec<_> = if let Some(ref tag) = params.tag {
-                    tasks.into_iter().filter(|t| t.tag == *tag).collect()
-                } else {
-                    tasks
-                };
+    fn mime_type(&self) -> &'static str {
+        match self {
+            Resource::Task(_) => "application/json",
+            Resource::File(_) | Resource::Today => "text/markdown",
+        }
+    }
 
-                if filtered.is_empty() {
-                    return format!("No tasks found matching '{}'", params.query);
+    /// Current content of the resource, read from disk.
+    fn read(&self) -> crate::error::Result<String> {
+        match self {
+            Resource::Task(id) => {
+                let (label, task) = writer::get_task(id)?;
+                serde_json::to_string_pretty(&TaskRecord::new(&label, &task))
+                    .map_err(|e| TlError::Other(format!("JSON error: {}", e)))
+            }
+            Resource::File(label) => {
+                let config = Config::load()?;
+                let entry = config
+                    .effective_files()
+                    .into_iter()
+                    .find(|f| &f.label == label)
+                    .ok_or_else(|| TlError::Config(format!("no log file labelled '{}'", label)))?;
+                let path = entry.resolved_path();
+                if !path.exists() {
+                    return Ok(String::new());
                 }
+                Ok(std::fs::read_to_string(path)?)
+            }
+            Resource::Today => writer::get_today(),
+        }
+    }
+}
+
+fn read_uri(uri: &str) -> crate::error::Result<String> {
+    Resource::parse(uri)
+        .ok_or_else(|| TlError::Other(format!("unknown resource '{}'", uri)))?
+        .read()
+}
 
-                let mut output = String::new();
-                for task in &filtered {
-                    let status = if task.done { "x" } else { " " };
-                    output.push_str(&format!("[{}] {} {}\n", status, task.id(), task.title));
-                    for note in &task.notes {
-                        output.push_str(&format!("      - {}\n", note.text));
+/// Modification time of every configured log file.
+fn file_mtimes() -> HashMap<PathBuf, Option<SystemTime>> {
+    let Ok(config) = Config::load() else {
+        return HashMap::new();
+    };
+    config
+        .all_file_paths()
+        .into_iter()
+        .map(|path| {
+            let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+            (path, modified)
+        })
+        .collect()
+}
+
+/// Poll the log files' mtimes and today's date and, when either moves,
+/// re-read every subscribed resource and notify the client about those whose
+/// content changed. `tl://today` changes at midnight without any file being
+/// touched, hence the date. Runs until the client goes away.
+async fn watch_files(subscriptions: Subscriptions, peer: Peer<RoleServer>) {
+    let mut last = (HashMap::new(), dates::today());
+    let mut ticker = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        ticker.tick().await;
+        let uris: Vec<String> = subscriptions.lock().unwrap().keys().cloned().collect();
+        if uris.is_empty() {
+            continue;
+        }
+
+        // Config loading, stat and reads are blocking; keep them off the
+        // async workers and don't hold the lock meanwhile.
+        let Ok(current) = tokio::task::spawn_blocking(|| (file_mtimes(), dates::today())).await
+        else {
+            return;
+        };
+        if current == last {
+            continue;
+        }
+        last = current;
+        let Ok(contents) = tokio::task::spawn_blocking(move || {
+            uris.into_iter()
+                .map(|uri| {
+                    let content = read_uri(&uri).ok();
+                    (uri, content)
+                })
+                .collect::<Vec<_>>()
+        })
+        .await
+        else {
+            return;
+        };
+
+        let changed: Vec<String> = {
+            let mut subscribed = subscriptions.lock().unwrap();
+            contents
+                .into_iter()
+                .filter_map(|(uri, content)| {
+                    // Skip URIs unsubscribed while we were reading
+                    let seen = subscribed.get_mut(&uri)?;
+                    if *seen == content {
+                        return None;
                     }
-                }
-                output
+                    *seen = content;
+                    Some(uri)
+                })
+                .collect()
+        };
+        for uri in changed {
+            let notified = peer
+                .notify_resource_updated(ResourceUpdatedNotificationParam { uri })
+                .await;
+            if notified.is_err() {
+                return;
             }
-            Err(e) => format!("Error: {}", e),
         }
     }
+}
 
-    /// Get the raw text of today's section from the log.
-    #[tool(description = "Get the raw text of today's section from the log.")]
-    fn get_today_section(&self) -> String {
-        match writer::get_today() {
-            Ok(text) => text,
-            Err(e) => format!("Error: {}", e),
+/// Map a failed resource read to a protocol error, keeping the `TlError`
+/// variant as the error code.
+fn resource_error(uri: &str, e: TlError) -> ErrorData {
+    let data = Some(json!({ "uri": uri, "error": e.code() }));
+    match e {
+        TlError::TaskNotFound(_) | TlError::Config(_) => {
+            ErrorData::resource_not_found(e.to_string(), data)
         }
+        _ => ErrorData::internal_error(e.to_string(), data),
     }
 }
 
 #[tool_handler]
+#[prompt_handler]
 impl ServerHandler for TlMcpServer {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             protocol_version: ProtocolVersion::V_2025_03_26,
             capabilities: ServerCapabilities::builder()
                 .enable_tools()
+                .enable_prompts()
+                .enable_resources()
+                .enable_resources_subscribe()
                 .build(),
             server_info: Implementation {
                 name: "tl".to_string(),
@@ -141,10 +849,126 @@ impl ServerHandler for TlMcpServer {
                 ..Default::default()
             },
             instructions: Some(
-                "Task log tool. Use create_task to add tasks, complete_task to mark done, add_note to annotate, search_tasks to find tasks.".to_string(),
+                "Task log tool. Use create_task to add tasks, complete_task to mark done, add_note to annotate, search_tasks to find tasks, get_task for one task's details. Resources tl://task/<id>, tl://file/<label> and tl://today can be read and subscribed to. Prompts: standup, triage, plan_day.".to_string(),
             ),
         }
     }
+
+    fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> impl Future<Output = Result<ListResourcesResult, ErrorData>> + Send + '_ {
+        let mut resources = vec![RawResource {
+            description: Some("Today's section from every log file".to_string()),
+            mime_type: Some(Resource::Today.mime_type().to_string()),
+            ..RawResource::new("tl://today", "today")
+        }
+        .no_annotation()];
+        if let Ok(config) = Config::load() {
+            for entry in config.effective_files() {
+                let uri = format!("tl://file/{}", entry.label);
+                resources.push(
+                    RawResource {
+                        description: Some(format!("Log file {}", entry.path)),
+                        mime_type: Some(Resource::File(entry.label.clone()).mime_type().to_string()),
+                        ..RawResource::new(uri, entry.label.clone())
+                    }
+                    .no_annotation(),
+                );
+            }
+        }
+        std::future::ready(Ok(ListResourcesResult {
+            resources,
+            ..Default::default()
+        }))
+    }
+
+    fn list_resource_templates(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> impl Future<Output = Result<ListResourceTemplatesResult, ErrorData>> + Send + '_ {
+        let template = |uri: &str, name: &str, description: &str, mime_type: &str| {
+            RawResourceTemplate {
+                uri_template: uri.to_string(),
+                name: name.to_string(),
+                title: None,
+                description: Some(description.to_string()),
+                mime_type: Some(mime_type.to_string()),
+                icons: None,
+            }
+            .no_annotation()
+        };
+        std::future::ready(Ok(ListResourceTemplatesResult {
+            resource_templates: vec![
+                template("tl://task/{id}", "task", "One task as JSON, by ID (e.g. tl://task/dev-3)", "application/json"),
+                template("tl://file/{label}", "file", "The markdown of a configured log file, by label", "text/markdown"),
+            ],
+            ..Default::default()
+        }))
+    }
+
+    fn read_resource(
+        &self,
+        request: ReadResourceRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> impl Future<Output = Result<ReadResourceResult, ErrorData>> + Send + '_ {
+        let uri = request.uri;
+        let result = match Resource::parse(&uri) {
+            None => Err(ErrorData::resource_not_found(
+                format!("unknown resource '{}'", uri),
+                None,
+            )),
+            Some(resource) => match resource.read() {
+                Ok(text) => Ok(ReadResourceResult {
+                    contents: vec![ResourceContents::TextResourceContents {
+                        uri,
+                        mime_type: Some(resource.mime_type().to_string()),
+                        text,
+                        meta: None,
+                    }],
+                }),
+                Err(e) => Err(resource_error(&uri, e)),
+            },
+        };
+        std::future::ready(result)
+    }
+
+    fn subscribe(
+        &self,
+        request: SubscribeRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> impl Future<Output = Result<(), ErrorData>> + Send + '_ {
+        let result = if Resource::parse(&request.uri).is_some() {
+            let content = read_uri(&request.uri).ok();
+            self.subscriptions.lock().unwrap().insert(request.uri, content);
+            Ok(())
+        } else {
+            Err(ErrorData::resource_not_found(
+                format!("unknown resource '{}'", request.uri),
+                None,
+            ))
+        };
+        std::future::ready(result)
+    }
+
+    fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> impl Future<Output = Result<(), ErrorData>> + Send + '_ {
+        self.subscriptions.lock().unwrap().remove(&request.uri);
+        std::future::ready(Ok(()))
+    }
+
+    fn on_initialized(
+        &self,
+        context: NotificationContext<RoleServer>,
+    ) -> impl Future<Output = ()> + Send + '_ {
+        tokio::spawn(watch_files(self.subscriptions.clone(), context.peer));
+        std::future::ready(())
+    }
 }
 
 pub fn run_mcp_server() -> crate::error::Result<()> {
@@ -0,0 +1,333 @@
+use crate::config::{FileEntry, FileMode, InsertPosition};
+use crate::error::{Result, TlError};
+use crate::parser::{self, Reference, Status, Task};
+use chrono::{NaiveDate, NaiveDateTime};
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// Output format for read commands.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Format {
+    /// Human-readable text
+    Text,
+    /// A single JSON array
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    /// Tab-separated values with a header row
+    Tsv,
+}
+
+/// Stable, machine-readable view of a task. Dates are ISO-8601 regardless of
+/// the configured `date_format`.
+#[derive(Debug, Serialize)]
+pub struct TaskRecord {
+    pub id: String,
+    pub tag: String,
+    pub number: u64,
+    pub title: String,
+    pub done: bool,
+    /// "open", "in-progress", "blocked", "done" or "cancelled".
+    pub status: String,
+    /// "urgent", "high", "med", "low" or "none".
+    pub priority: String,
+    /// Date of the enclosing section (`YYYY-MM-DD`), null under freeform headers.
+    pub date: Option<String>,
+    /// When the task was completed (`YYYY-MM-DDTHH:MM:SS`).
+    pub completed_at: Option<String>,
+    /// Due date (`YYYY-MM-DD`).
+    pub due: Option<String>,
+    /// Scheduled date (`YYYY-MM-DD`).
+    pub scheduled: Option<String>,
+    /// Label of the file the task lives in.
+    pub file: String,
+    /// ID of the parent task, for subtasks.
+    pub parent: Option<String>,
+    /// `#label` tokens from the title, without the `#`.
+    pub labels: Vec<String>,
+    /// `@person` tokens from the title, without the `@`.
+    pub assignees: Vec<String>,
+    /// IDs from `blocked-by:` markers.
+    pub blocked_by: Vec<String>,
+    /// IDs from `blocks:` markers.
+    pub blocks: Vec<String>,
+    pub notes: Vec<NoteRecord>,
+    /// Task IDs referenced from the title or notes.
+    pub links: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NoteRecord {
+    /// When the note was written (`YYYY-MM-DDTHH:MM:SS`).
+    pub created_at: Option<String>,
+    /// Who wrote it, e.g. "cli" or "agent".
+    pub author: Option<String>,
+    pub text: String,
+}
+
+impl TaskRecord {
+    pub fn new(file: &str, task: &Task) -> Self {
+        Self {
+            id: task.id(),
+            tag: task.tag.clone(),
+            number: task.number,
+            title: task.title.clone(),
+            done: task.status == Status::Done,
+            status: task.status.name().to_string(),
+            priority: task.priority.name().to_string(),
+            date: task.section_date.map(iso_date),
+            completed_at: task.completed_at.map(iso_datetime),
+            due: task.due.map(iso_date),
+            scheduled: task.scheduled.map(iso_date),
+            file: file.to_string(),
+            parent: task.parent.clone(),
+            labels: task.labels.clone(),
+            assignees: task.assignees.clone(),
+            blocked_by: task.blocked_by.clone(),
+            blocks: task.blocks.clone(),
+            notes: task
+                .notes
+                .iter()
+                .map(|n| NoteRecord {
+                    created_at: n.created_at.map(iso_datetime),
+                    author: n.author.clone(),
+                    text: n.text.clone(),
+                })
+                .collect(),
+            links: parser::task_links(task),
+        }
+    }
+}
+
+/// A task in `tl deps` output, with where it sits relative to the task asked
+/// about.
+#[derive(Debug, Serialize)]
+pub struct DepRecord {
+    /// "root", "blocker" or "dependent".
+    pub relation: String,
+    /// 0 for the root, 1 for its direct blockers and dependents, 2 for the
+    /// blockers of those blockers, and so on.
+    pub depth: usize,
+    #[serde(flatten)]
+    pub task: TaskRecord,
+}
+
+impl DepRecord {
+    pub fn new(relation: &str, depth: usize, file: &str, task: &Task) -> Self {
+        Self {
+            relation: relation.to_string(),
+            depth,
+            task: TaskRecord::new(file, task),
+        }
+    }
+}
+
+fn iso_date(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+fn iso_datetime(at: NaiveDateTime) -> String {
+    at.format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+/// Machine-readable view of a backlink: a task that mentions another one.
+#[derive(Debug, Serialize)]
+pub struct ReferenceRecord {
+    /// ID of the task holding the mention.
+    pub id: String,
+    pub file: String,
+    /// 1-based line number in the file.
+    pub line: usize,
+    /// "title" or "note".
+    pub kind: String,
+    pub text: String,
+}
+
+impl From<&Reference> for ReferenceRecord {
+    fn from(r: &Reference) -> Self {
+        Self {
+            id: r.from.clone(),
+            file: r.file.clone(),
+            line: r.line + 1,
+            kind: if r.in_note { "note" } else { "title" }.to_string(),
+            text: r.text.clone(),
+        }
+    }
+}
+
+/// Machine-readable view of a configured log file.
+#[derive(Debug, Serialize)]
+pub struct FileRecord {
+    pub label: String,
+    pub path: String,
+    /// "variable" or "fixed".
+    pub mode: String,
+    pub tags: Vec<String>,
+    /// "top" or "bottom".
+    pub insert: String,
+}
+
+impl From<&FileEntry> for FileRecord {
+    fn from(f: &FileEntry) -> Self {
+        Self {
+            label: f.label.clone(),
+            path: f.path.clone(),
+            mode: match f.mode {
+                FileMode::Variable => "variable".to_string(),
+                FileMode::Fixed => "fixed".to_string(),
+            },
+            tags: f.tags.clone(),
+            insert: match f.insert {
+                InsertPosition::Top => "top".to_string(),
+                InsertPosition::Bottom => "bottom".to_string(),
+            },
+        }
+    }
+}
+
+/// Column layout for TSV output.
+pub trait TsvRow {
+    fn header() -> &'static [&'static str];
+    fn row(&self) -> Vec<String>;
+}
+
+impl TsvRow for TaskRecord {
+    fn header() -> &'static [&'static str] {
+        &[
+            "id",
+            "tag",
+            "number",
+            "done",
+            "status",
+            "priority",
+            "date",
+            "completed_at",
+            "due",
+            "scheduled",
+            "file",
+            "parent",
+            "labels",
+            "assignees",
+            "blocked_by",
+            "blocks",
+            "notes",
+            "title",
+        ]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.tag.clone(),
+            self.number.to_string(),
+            self.done.to_string(),
+            self.status.clone(),
+            self.priority.clone(),
+            self.date.clone().unwrap_or_default(),
+            self.completed_at.clone().unwrap_or_default(),
+            self.due.clone().unwrap_or_default(),
+            self.scheduled.clone().unwrap_or_default(),
+            self.file.clone(),
+            self.parent.clone().unwrap_or_default(),
+            self.labels.join(","),
+            self.assignees.join(","),
+            self.blocked_by.join(","),
+            self.blocks.join(","),
+            self.notes.len().to_string(),
+            self.title.clone(),
+        ]
+    }
+}
+
+impl TsvRow for DepRecord {
+    fn header() -> &'static [&'static str] {
+        &[
+            "relation",
+            "depth",
+            "id",
+            "tag",
+            "number",
+            "done",
+            "status",
+            "priority",
+            "date",
+            "completed_at",
+            "due",
+            "scheduled",
+            "file",
+            "parent",
+            "labels",
+            "assignees",
+            "blocked_by",
+            "blocks",
+            "notes",
+            "title",
+        ]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let mut row = vec![self.relation.clone(), self.depth.to_string()];
+        row.extend(self.task.row());
+        row
+    }
+}
+
+impl TsvRow for ReferenceRecord {
+    fn header() -> &'static [&'static str] {
+        &["id", "file", "line", "kind", "text"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.file.clone(),
+            self.line.to_string(),
+            self.kind.clone(),
+            self.text.clone(),
+        ]
+    }
+}
+
+impl TsvRow for FileRecord {
+    fn header() -> &'static [&'static str] {
+        &["label", "path", "mode", "tags", "insert"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.label.clone(),
+            self.path.clone(),
+            self.mode.clone(),
+            self.tags.join(","),
+            self.insert.clone(),
+        ]
+    }
+}
+
+/// Render records in a machine-readable format. `Format::Text` is handled by
+/// each command itself and is rejected here.
+pub fn render<T: Serialize + TsvRow>(records: &[T], format: Format) -> Result<String> {
+    let json_err = |e: serde_json::Error| TlError::Other(format!("JSON error: {}", e));
+    match format {
+        Format::Json => serde_json::to_string_pretty(records).map_err(json_err),
+        Format::Ndjson => records
+            .iter()
+            .map(|r| serde_json::to_string(r).map_err(json_err))
+            .collect::<Result<Vec<_>>>()
+            .map(|lines| lines.join("\n")),
+        Format::Tsv => {
+            let mut lines = vec![T::header().join("\t")];
+            for record in records {
+                let cells: Vec<String> = record.row().iter().map(|c| tsv_escape(c)).collect();
+                lines.push(cells.join("\t"));
+            }
+            Ok(lines.join("\n"))
+        }
+        Format::Text => Err(TlError::Other("text output is rendered by the command".to_string())),
+    }
+}
+
+/// Keep each value on one line and in one column.
+fn tsv_escape(value: &str) -> String {
+    value.replace(['\t', '\n', '\r'], " ")
+}
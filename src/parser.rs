@@ -1,10 +1,14 @@
+use crate::dates::{self, DateFormat};
 use crate::error::{Result, TlError};
+use crate::recurrence::Recurrence;
+use chrono::{NaiveDate, NaiveDateTime};
 use regex::Regex;
+use std::collections::{HashMap, HashSet};
 use std::sync::LazyLock;
 
 static TASK_RE: LazyLock<Regex> = LazyLock::new(|| {
-    // Captures: indent, done marker, tag, number, optional priority (!), title
-    Regex::new(r"^(\s*)- \[([ x])\] ([a-z][a-z0-9]*)-(\d+)(!)? (.+)$").unwrap()
+    // Captures: indent, done marker, tag, number, optional priority marker, title
+    Regex::new(r"^(\s*)- \[([ x/?-])\] ([a-z][a-z0-9]*)-(\d+)(!!!|!!|![pP][0-3]|!)? (.+)$").unwrap()
 });
 
 static SECTION_RE: LazyLock<Regex> = LazyLock::new(|| {
@@ -15,49 +19,421 @@ static NOTE_RE: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"^(\s+)- (.+)$").unwrap()
 });
 
+static NOTE_META_RE: LazyLock<Regex> = LazyLock::new(|| {
+    // Captures: optional "[stamp]", optional "(by author)", body
+    Regex::new(r"^(?:\[([^\]]+)\] )?(?:\(by ([A-Za-z0-9_.-]+)\) )?(.*)$").unwrap()
+});
+
+static DUE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    // Captures: marker, date. "due:2026-11-01" or "📅 2026-11-01"
+    Regex::new(r"(?:^|\s)(due:|📅 ?)(\S+)").unwrap()
+});
+
+static SCHEDULED_RE: LazyLock<Regex> = LazyLock::new(|| {
+    // Captures: marker, date. "scheduled:2026-11-01" or "⏳ 2026-11-01"
+    Regex::new(r"(?:^|\s)(scheduled:|⏳ ?)(\S+)").unwrap()
+});
+
+static RECUR_RE: LazyLock<Regex> = LazyLock::new(|| {
+    // Captures: rule. "every:1w", "every:monday"
+    Regex::new(r"(?:^|\s)every:(\S+)").unwrap()
+});
+
+static LABEL_RE: LazyLock<Regex> = LazyLock::new(|| {
+    // Captures: label. "#security"; "#42" is an issue number, not a label
+    Regex::new(r"(?:^|\s)#([A-Za-z][\w-]*)").unwrap()
+});
+
+static ASSIGNEE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    // Captures: assignee. "@alice"
+    Regex::new(r"(?:^|\s)@([A-Za-z0-9][\w.-]*)").unwrap()
+});
+
+static BLOCKED_BY_RE: LazyLock<Regex> = LazyLock::new(|| {
+    // Captures: comma-separated IDs. "blocked-by:dev-3" or "blocked-by:dev-3,ops-1"
+    Regex::new(r"(?:^|\s)blocked-by:(\S+)").unwrap()
+});
+
+static BLOCKS_RE: LazyLock<Regex> = LazyLock::new(|| {
+    // Captures: comma-separated IDs. "blocks:dev-7"
+    Regex::new(r"(?:^|\s)blocks:(\S+)").unwrap()
+});
+
+static ALIAS_RE: LazyLock<Regex> = LazyLock::new(|| {
+    // Captures: the task's previous ID. "was dev-4", left by `tl retag`
+    Regex::new(r"^was ([a-z][a-z0-9]*-\d+)$").unwrap()
+});
+
+static TASKLIKE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    // A checkbox followed by something ID-shaped, e.g. "- [X] dev-3 ..." or
+    // "- [ ]dev-3"; used to spot task lines that fail TASK_RE
+    Regex::new(r"^\s*-\s*\[[^\]]?\]\s*[A-Za-z][A-Za-z0-9]*-\d+").unwrap()
+});
+
 static LINK_RE: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"([a-z][a-z0-9]*-\d+)").unwrap()
 });
 
-#[derive(Debug, Clone)]
+/// Task priority, from the marker right after the ID.
+///
+/// `!!!` and `!p0` are urgent, `!`, `!!` and `!p1` are high, `!p2` is medium
+/// and `!p3` is low. A plain `!` has always meant high, so old logs keep their
+/// meaning, and adding `!`s never lowers the level.
+/// Tasks remember the spelling they were written with (`Task::priority_marker`),
+/// so rewriting a line keeps `!p0` as `!p0` unless the level changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Priority {
+    #[default]
+    None,
+    Low,
+    Med,
+    High,
+    Urgent,
+}
+
+impl Priority {
+    fn from_marker(marker: &str) -> Self {
+        match marker.to_lowercase().as_str() {
+            "!!!" | "!p0" => Self::Urgent,
+            "!" | "!!" | "!p1" => Self::High,
+            "!p2" => Self::Med,
+            "!p3" => Self::Low,
+            _ => Self::None,
+        }
+    }
+
+    /// The marker written after the task ID for a new or re-prioritised task.
+    pub fn marker(self) -> &'static str {
+        match self {
+            Self::None => "",
+            Self::Low => "!p3",
+            Self::Med => "!p2",
+            Self::High => "!",
+            Self::Urgent => "!p0",
+        }
+    }
+
+    /// Parse "urgent", "high", "med", "low" or "none" (also "medium",
+    /// "p0".."p3").
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "urgent" | "p0" => Some(Self::Urgent),
+            "high" | "p1" => Some(Self::High),
+            "med" | "medium" | "p2" => Some(Self::Med),
+            "low" | "p3" => Some(Self::Low),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Low => "low",
+            Self::Med => "med",
+            Self::High => "high",
+            Self::Urgent => "urgent",
+        }
+    }
+
+    /// The level after this one when cycling: none -> urgent -> high -> med ->
+    /// low -> none.
+    pub fn cycle(self) -> Self {
+        match self {
+            Self::None => Self::Urgent,
+            Self::Urgent => Self::High,
+            Self::High => Self::Med,
+            Self::Med => Self::Low,
+            Self::Low => Self::None,
+        }
+    }
+}
+
+/// Task state, from the checkbox: `[ ]` open, `[/]` in progress, `[?]`
+/// blocked, `[x]` done, `[-]` cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Status {
+    #[default]
+    Open,
+    InProgress,
+    Blocked,
+    Done,
+    Cancelled,
+}
+
+impl Status {
+    fn from_marker(marker: &str) -> Self {
+        match marker {
+            "/" => Self::InProgress,
+            "?" => Self::Blocked,
+            "x" => Self::Done,
+            "-" => Self::Cancelled,
+            _ => Self::Open,
+        }
+    }
+
+    /// The character between the checkbox brackets.
+    pub fn marker(self) -> char {
+        match self {
+            Self::Open => ' ',
+            Self::InProgress => '/',
+            Self::Blocked => '?',
+            Self::Done => 'x',
+            Self::Cancelled => '-',
+        }
+    }
+
+    /// Parse a state name as used on the command line.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "open" => Some(Self::Open),
+            "in-progress" | "started" => Some(Self::InProgress),
+            "blocked" => Some(Self::Blocked),
+            "done" => Some(Self::Done),
+            "cancelled" | "canceled" => Some(Self::Cancelled),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::InProgress => "in-progress",
+            Self::Blocked => "blocked",
+            Self::Done => "done",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    /// Done or cancelled: no more work is expected.
+    pub fn is_closed(self) -> bool {
+        matches!(self, Self::Done | Self::Cancelled)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct Task {
     pub line_number: usize,
     pub indent: String,
-    pub done: bool,
+    pub status: Status,
     pub tag: String,
     pub number: u64,
     pub title: String,
     pub notes: Vec<Note>,
+    /// Raw text of the enclosing `### ` header.
     pub date: String,
-    pub priority: bool,
+    /// The enclosing header parsed as a date, if it is one.
+    pub section_date: Option<NaiveDate>,
+    pub priority: Priority,
+    /// The priority marker as written in the log (e.g. "!!!" or "!p1"), kept so
+    /// rewriting the line doesn't respell it. See `written_priority`.
+    pub priority_marker: String,
+    /// When the task was marked done or cancelled, from the trailing
+    /// " (<date> <time>)" stamp.
+    pub completed_at: Option<NaiveDateTime>,
+    /// ID of the task this one is indented under, if it's a subtask.
+    pub parent: Option<String>,
+    /// From a `due:<date>` / `📅 <date>` marker in the title.
+    pub due: Option<NaiveDate>,
+    /// From a `scheduled:<date>` / `⏳ <date>` marker in the title.
+    pub scheduled: Option<NaiveDate>,
+    /// From an `every:<rule>` marker in the title.
+    pub recurrence: Option<Recurrence>,
+    /// `#label` tokens in the title, without the `#`.
+    pub labels: Vec<String>,
+    /// `@person` tokens in the title, without the `@`.
+    pub assignees: Vec<String>,
+    /// IDs from `blocked-by:<id>[,<id>...]` markers in the title.
+    pub blocked_by: Vec<String>,
+    /// IDs from `blocks:<id>[,<id>...]` markers in the title.
+    pub blocks: Vec<String>,
 }
 
 impl Task {
     pub fn id(&self) -> String {
         format!("{}-{}", self.tag, self.number)
     }
+
+    /// Previous IDs of this task, from "was <id>" notes left by a retag.
+    pub fn aliases(&self) -> Vec<String> {
+        self.notes.iter().filter_map(|n| alias_of(&n.text)).collect()
+    }
+
+    pub fn has_label(&self, label: &str) -> bool {
+        let label = label.trim_start_matches('#');
+        self.labels.iter().any(|l| l.eq_ignore_ascii_case(label))
+    }
+
+    pub fn has_assignee(&self, assignee: &str) -> bool {
+        let assignee = assignee.trim_start_matches('@');
+        self.assignees.iter().any(|a| a.eq_ignore_ascii_case(assignee))
+    }
+
+    /// Open and due before `today`.
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        !self.status.is_closed() && self.due.is_some_and(|due| due < today)
+    }
+
+    /// The priority marker to show or write: the original spelling while it
+    /// still means `priority`, otherwise the standard marker for the level.
+    pub fn written_priority(&self) -> &str {
+        if Priority::from_marker(&self.priority_marker) == self.priority {
+            &self.priority_marker
+        } else {
+            self.priority.marker()
+        }
+    }
+
+    /// Rebuild the markdown line for this task from its fields.
+    pub fn render(&self, fmt: &DateFormat) -> String {
+        let mut line = format!(
+            "{}- [{}] {}{} {}",
+            self.indent,
+            self.status.marker(),
+            self.id(),
+            self.written_priority(),
+            self.title
+        );
+        if let Some(at) = self.completed_at {
+            line.push_str(&format!(" ({})", fmt.format_timestamp(at)));
+        }
+        line
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Note {
     pub line_number: usize,
+    /// From the leading "[<date> <time>]" stamp, if present.
+    pub created_at: Option<NaiveDateTime>,
+    /// Who wrote the note, from a "(by <author>)" marker after the stamp
+    /// (e.g. "cli", "agent").
+    pub author: Option<String>,
+    /// The note body, without stamp or author marker.
     pub text: String,
 }
 
+impl Note {
+    /// The note as written after the "- " bullet: "[<stamp>] (by <author>) <text>".
+    pub fn display(&self, fmt: &DateFormat) -> String {
+        let mut out = String::new();
+        if let Some(at) = self.created_at {
+            out.push_str(&format!("[{}] ", fmt.format_timestamp(at)));
+        }
+        if let Some(ref author) = self.author {
+            out.push_str(&format!("(by {}) ", author));
+        }
+        out.push_str(&self.text);
+        out
+    }
+
+    /// Rebuild the markdown line for this note at the given indentation.
+    pub fn render(&self, indent: &str, fmt: &DateFormat) -> String {
+        format!("{}- {}", indent, self.display(fmt))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Section {
+    /// Raw header text after `### `.
+    pub header: String,
+    /// The header parsed as a date. `None` for freeform headers like `### Ideas`.
+    pub date: Option<NaiveDate>,
     pub tasks: Vec<Task>,
 }
 
-pub fn parse_task_line(line: &str) -> Option<(String, bool, String, u64, bool, String)> {
+/// Parse a task line into a `Task` with no notes, line number or section.
+pub fn parse_task_line(line: &str, fmt: &DateFormat) -> Option<Task> {
     let caps = TASK_RE.captures(line)?;
-    let indent = caps[1].to_string();
-    let done = &caps[2] == "x";
-    let tag = caps[3].to_string();
-    let number: u64 = caps[4].parse().ok()?;
-    let priority = caps.get(5).is_some();
-    let title = caps[6].to_string();
-    Some((indent, done, tag, number, priority, title))
+    let (title, completed_at) = split_completion(&caps[6], fmt);
+    Some(Task {
+        indent: caps[1].to_string(),
+        status: Status::from_marker(&caps[2]),
+        tag: caps[3].to_string(),
+        number: caps[4].parse().ok()?,
+        priority: caps
+            .get(5)
+            .map_or(Priority::None, |m| Priority::from_marker(m.as_str())),
+        priority_marker: caps.get(5).map_or("", |m| m.as_str()).to_string(),
+        title: title.to_string(),
+        completed_at,
+        due: marker_date(&DUE_RE, title, fmt),
+        scheduled: marker_date(&SCHEDULED_RE, title, fmt),
+        recurrence: RECUR_RE
+            .captures(title)
+            .and_then(|caps| Recurrence::parse(&caps[1])),
+        labels: title_tokens(&LABEL_RE, title),
+        assignees: title_tokens(&ASSIGNEE_RE, title),
+        blocked_by: marker_ids(&BLOCKED_BY_RE, title),
+        blocks: marker_ids(&BLOCKS_RE, title),
+        ..Default::default()
+    })
+}
+
+/// Distinct tokens captured by `re` in a title, in order of appearance.
+fn title_tokens(re: &Regex, title: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = Vec::new();
+    for caps in re.captures_iter(title) {
+        if !tokens.contains(&caps[1].to_string()) {
+            tokens.push(caps[1].to_string());
+        }
+    }
+    tokens
+}
+
+/// Task IDs listed in every `re` marker in a title.
+fn marker_ids(re: &Regex, title: &str) -> Vec<String> {
+    let mut ids: Vec<String> = Vec::new();
+    for caps in re.captures_iter(title) {
+        for id in extract_links(&caps[1]) {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+    ids
+}
+
+/// The date in the first `re` marker in a title, if it parses.
+fn marker_date(re: &Regex, title: &str, fmt: &DateFormat) -> Option<NaiveDate> {
+    re.captures(title).and_then(|caps| fmt.parse(&caps[2]))
+}
+
+/// Set, replace or (with `None`) remove the due marker in a title. An
+/// existing marker keeps its style (`due:` or `📅`); new markers use `due:`.
+/// Marker dates are always written as ISO-8601.
+pub fn with_due(title: &str, due: Option<NaiveDate>) -> String {
+    with_date_marker(&DUE_RE, "due:", title, due)
+}
+
+/// `with_due` for the scheduled marker (`scheduled:` or `⏳`).
+pub fn with_scheduled(title: &str, scheduled: Option<NaiveDate>) -> String {
+    with_date_marker(&SCHEDULED_RE, "scheduled:", title, scheduled)
+}
+
+fn with_date_marker(re: &Regex, prefix: &str, title: &str, date: Option<NaiveDate>) -> String {
+    let Some(caps) = re.captures(title) else {
+        return match date {
+            Some(date) => format!("{} {}{}", title, prefix, date.format("%Y-%m-%d")),
+            None => title.to_string(),
+        };
+    };
+    match date {
+        Some(date) => {
+            let old = caps.get(2).unwrap();
+            format!("{}{}{}", &title[..old.start()], date.format("%Y-%m-%d"), &title[old.end()..])
+        }
+        None => {
+            let whole = caps.get(0).unwrap();
+            format!("{}{}", &title[..whole.start()], &title[whole.end()..])
+        }
+    }
+}
+
+/// Whether `line` looks like it was meant to be a task line. True for real
+/// task lines too; callers check `parse_task_line` first.
+pub fn looks_like_task(line: &str) -> bool {
+    TASKLIKE_RE.is_match(line)
 }
 
 pub fn is_section_header(line: &str) -> Option<String> {
@@ -69,6 +445,94 @@ pub fn is_note_line(line: &str) -> Option<(String, String)> {
     Some((caps[1].to_string(), caps[2].to_string()))
 }
 
+/// The indentation notes in this file already use relative to their task
+/// (e.g. "\t" or four spaces), picking the most common one. None if the file
+/// has no notes yet.
+pub fn detect_note_indent(content: &str) -> Option<String> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    let mut task_indent: Option<String> = None;
+
+    for line in content.lines() {
+        if let Some(caps) = TASK_RE.captures(line) {
+            task_indent = Some(caps[1].to_string());
+            continue;
+        }
+        let Some(parent) = task_indent.as_deref() else {
+            continue;
+        };
+        match is_note_line(line) {
+            Some((indent, _)) if indent.len() > parent.len() && indent.starts_with(parent) => {
+                let relative = &indent[parent.len()..];
+                match counts.iter_mut().find(|(i, _)| i == relative) {
+                    Some((_, n)) => *n += 1,
+                    None => counts.push((relative.to_string(), 1)),
+                }
+            }
+            _ => task_indent = None,
+        }
+    }
+
+    // On a tie the style seen first wins
+    counts
+        .into_iter()
+        .rev()
+        .max_by_key(|(_, n)| *n)
+        .map(|(indent, _)| indent)
+}
+
+/// Split a trailing completion stamp like " (17/10/2026 09:41AM)" off a task
+/// title. Parentheses that don't hold a timestamp are left in the title.
+fn split_completion<'a>(title: &'a str, fmt: &DateFormat) -> (&'a str, Option<NaiveDateTime>) {
+    if let Some(inner) = title.strip_suffix(')') {
+        if let Some(pos) = inner.rfind(" (") {
+            if let Some(at) = fmt.parse_timestamp(&inner[pos + 2..]) {
+                return (&title[..pos], Some(at));
+            }
+        }
+    }
+    (title, None)
+}
+
+/// Split a note's text into its stamp, author and body. A bracketed prefix
+/// that isn't a timestamp stays part of the body.
+pub fn parse_note_text(text: &str, fmt: &DateFormat) -> Note {
+    let plain = Note {
+        text: text.to_string(),
+        ..Default::default()
+    };
+    let Some(caps) = NOTE_META_RE.captures(text) else {
+        return plain;
+    };
+    let created_at = match caps.get(1) {
+        Some(stamp) => match fmt.parse_timestamp(stamp.as_str()) {
+            Some(at) => Some(at),
+            None => return plain,
+        },
+        None => None,
+    };
+    Note {
+        created_at,
+        author: caps.get(2).map(|a| a.as_str().to_string()),
+        text: caps[3].to_string(),
+        ..Default::default()
+    }
+}
+
+/// A title or note line in one task that mentions another task's ID.
+#[derive(Debug, Clone)]
+pub struct Reference {
+    /// ID of the task whose title or note holds the mention.
+    pub from: String,
+    /// Label of the file it lives in.
+    pub file: String,
+    /// 0-based line number.
+    pub line: usize,
+    /// True if the mention is in a note rather than the title.
+    pub in_note: bool,
+    /// The title or note text.
+    pub text: String,
+}
+
 /// Extract task ID references (e.g. "bb-5", "osv-12") from text.
 pub fn extract_links(text: &str) -> Vec<String> {
     LINK_RE
@@ -77,100 +541,237 @@ pub fn extract_links(text: &str) -> Vec<String> {
         .collect()
 }
 
+/// Rewrite task ID references in `text`. `map` gets each ID `extract_links`
+/// would find and returns its replacement, or None to leave it alone.
+pub fn rewrite_links(text: &str, map: impl Fn(&str) -> Option<String>) -> String {
+    LINK_RE
+        .replace_all(text, |caps: &regex::Captures| {
+            map(&caps[1]).unwrap_or_else(|| caps[1].to_string())
+        })
+        .into_owned()
+}
+
+/// The previous ID recorded by a "was <id>" alias note.
+pub fn alias_of(note_text: &str) -> Option<String> {
+    ALIAS_RE.captures(note_text.trim()).map(|c| c[1].to_string())
+}
+
+/// All distinct task IDs referenced from a task's title and notes, excluding
+/// the task itself and its aliases, in order of appearance.
+pub fn task_links(task: &Task) -> Vec<String> {
+    let task_id = task.id();
+    let mut links: Vec<String> = Vec::new();
+    let notes = task.notes.iter().filter(|n| alias_of(&n.text).is_none());
+    let texts = std::iter::once(&task.title).chain(notes.map(|n| &n.text));
+    for text in texts {
+        for link in extract_links(text) {
+            if link != task_id && !links.contains(&link) {
+                links.push(link);
+            }
+        }
+    }
+    links
+}
+
 /// Parse the last `scan_window` lines of the log file.
 /// Returns all sections found with their tasks and notes.
-pub fn parse_log(content: &str, scan_window: usize) -> Vec<Section> {
+pub fn parse_log(content: &str, scan_window: usize, fmt: &DateFormat) -> Vec<Section> {
     let all_lines: Vec<&str> = content.lines().collect();
     let total = all_lines.len();
-    let start = if total > scan_window {
-        total - scan_window
-    } else {
-        0
-    };
+    let start = total.saturating_sub(scan_window);
     let lines = &all_lines[start..];
     let offset = start;
 
     let mut sections: Vec<Section> = Vec::new();
-    let mut current_task: Option<Task> = None;
-    let mut current_date = String::new();
+    // Indices (into the current section's tasks) of the task being read and
+    // its open ancestors, outermost first. A line belongs to the innermost
+    // task it is indented deeper than.
+    let mut open: Vec<usize> = Vec::new();
 
     for (i, line) in lines.iter().enumerate() {
         let abs_line = offset + i;
 
         if let Some(date) = is_section_header(line) {
-            // Flush current task
-            if let Some(task) = current_task.take() {
-                if let Some(sec) = sections.last_mut() {
-                    sec.tasks.push(task);
-                }
-            }
-            current_date = date;
+            open.clear();
             sections.push(Section {
+                date: fmt.parse(&date),
+                header: date,
                 tasks: Vec::new(),
             });
             continue;
         }
 
-        if let Some((indent, done, tag, number, priority, title)) = parse_task_line(line) {
-            // Flush previous task
-            if let Some(task) = current_task.take() {
-                if let Some(sec) = sections.last_mut() {
-                    sec.tasks.push(task);
-                }
-            }
-            current_task = Some(Task {
+        let Some(sec) = sections.last_mut() else {
+            // Tasks and notes before the first header aren't tracked
+            continue;
+        };
+
+        if let Some(task) = parse_task_line(line, fmt) {
+            close_shallower(&mut open, &sec.tasks, task.indent.len());
+            let parent = open.last().map(|&idx| sec.tasks[idx].id());
+            open.push(sec.tasks.len());
+            sec.tasks.push(Task {
                 line_number: abs_line,
-                indent,
-                done,
-                tag,
-                number,
-                title,
-                notes: Vec::new(),
-                date: current_date.clone(),
-                priority,
+                date: sec.header.clone(),
+                section_date: sec.date,
+                parent,
+                ..task
             });
             continue;
         }
 
         if let Some((indent, text)) = is_note_line(line) {
-            if let Some(ref mut task) = current_task {
-                // Only count as note if indented deeper than the task
-                if indent.len() > task.indent.len() {
-                    task.notes.push(Note {
-                        line_number: abs_line,
-                        text,
-                    });
-                    continue;
-                }
-            }
-            // Not a note belonging to a task — could be freeform indented bullet.
-            // Flush current task since indentation broke.
-            if let Some(task) = current_task.take() {
-                if let Some(sec) = sections.last_mut() {
-                    sec.tasks.push(task);
-                }
+            // Only count as note if indented deeper than a task; otherwise
+            // it's a freeform indented bullet and ends the task.
+            close_shallower(&mut open, &sec.tasks, indent.len());
+            if let Some(&idx) = open.last() {
+                sec.tasks[idx].notes.push(Note {
+                    line_number: abs_line,
+                    ..parse_note_text(&text, fmt)
+                });
             }
             continue;
         }
 
-        // Any other line: if it's not blank and not indented more, flush current task
+        // Any other non-blank line ends the current task
         if !line.trim().is_empty() {
-            if let Some(task) = current_task.take() {
-                if let Some(sec) = sections.last_mut() {
-                    sec.tasks.push(task);
-                }
+            open.clear();
+        }
+    }
+
+    sections
+}
+
+/// All tasks nested under `task` (children, grandchildren, ...), in file order.
+pub fn descendants<'a>(sections: &'a [Section], task: &Task) -> Vec<&'a Task> {
+    let Some(sec) = sections
+        .iter()
+        .find(|s| s.tasks.iter().any(|t| t.line_number == task.line_number))
+    else {
+        return Vec::new();
+    };
+    let mut ids = vec![task.id()];
+    let mut found = Vec::new();
+    for t in sec.tasks.iter().filter(|t| t.line_number > task.line_number) {
+        if t.parent.as_ref().is_some_and(|p| ids.contains(p)) {
+            ids.push(t.id());
+            found.push(t);
+        }
+    }
+    found
+}
+
+/// (done, total) over the direct subtasks of task `id`, or None if it has
+/// none. Cancelled subtasks don't count.
+pub fn subtask_progress<'a>(
+    tasks: impl IntoIterator<Item = &'a Task>,
+    id: &str,
+) -> Option<(usize, usize)> {
+    let (done, total) = tasks
+        .into_iter()
+        .filter(|t| t.parent.as_deref() == Some(id) && t.status != Status::Cancelled)
+        .fold((0, 0), |(done, total), t| {
+            (done + (t.status == Status::Done) as usize, total + 1)
+        });
+    (total > 0).then_some((done, total))
+}
+
+/// Tasks that `task` depends on: its own `blocked-by:` IDs plus any task that
+/// says it `blocks:` this one. IDs not found in `tasks` are skipped.
+pub fn blockers<'a>(tasks: &[&'a Task], task: &Task) -> Vec<&'a Task> {
+    let id = task.id();
+    let mut found: Vec<&Task> = Vec::new();
+    for &t in tasks {
+        let listed = task.blocked_by.contains(&t.id()) || t.blocks.contains(&id);
+        if listed && t.id() != id && !found.iter().any(|f| f.id() == t.id()) {
+            found.push(t);
+        }
+    }
+    found
+}
+
+/// Blockers of `task` that aren't done or cancelled yet.
+pub fn open_blockers<'a>(tasks: &[&'a Task], task: &Task) -> Vec<&'a Task> {
+    blockers(tasks, task)
+        .into_iter()
+        .filter(|t| !t.status.is_closed())
+        .collect()
+}
+
+/// Tasks that depend on `task`, i.e. that have it among their `blockers`.
+pub fn dependents<'a>(tasks: &[&'a Task], task: &Task) -> Vec<&'a Task> {
+    tasks
+        .iter()
+        .copied()
+        .filter(|t| !blockers(&[task], t).is_empty())
+        .collect()
+}
+
+/// Everything `root` waits on, depth-first with the depth of each entry:
+/// its direct blockers are at depth 1, their blockers at depth 2, and so on.
+/// Each task is listed once, so cycles terminate.
+pub fn blocker_tree<'a>(tasks: &[&'a Task], root: &Task) -> Vec<(usize, &'a Task)> {
+    fn walk<'a>(
+        tasks: &[&'a Task],
+        task: &Task,
+        depth: usize,
+        visited: &mut HashSet<String>,
+        out: &mut Vec<(usize, &'a Task)>,
+    ) {
+        for blocker in blockers(tasks, task) {
+            if visited.insert(blocker.id()) {
+                out.push((depth, blocker));
+                walk(tasks, blocker, depth + 1, visited, out);
             }
         }
     }
+    let mut out = Vec::new();
+    let mut visited = HashSet::from([root.id()]);
+    walk(tasks, root, 1, &mut visited, &mut out);
+    out
+}
 
-    // Flush last task
-    if let Some(task) = current_task.take() {
-        if let Some(sec) = sections.last_mut() {
-            sec.tasks.push(task);
+/// IDs of the tasks that have at least one open blocker. Same answer as
+/// calling `open_blockers` on each task, but in a single pass.
+pub fn waiting_ids(tasks: &[&Task]) -> HashSet<String> {
+    let open: HashMap<String, bool> = tasks
+        .iter()
+        .map(|t| (t.id(), !t.status.is_closed()))
+        .collect();
+    let mut waiting = HashSet::new();
+    for t in tasks {
+        let id = t.id();
+        if t.blocked_by.iter().any(|b| *b != id && open.get(b) == Some(&true)) {
+            waiting.insert(id.clone());
+        }
+        if !t.status.is_closed() {
+            waiting.extend(
+                t.blocks
+                    .iter()
+                    .filter(|b| **b != id && open.contains_key(*b))
+                    .cloned(),
+            );
         }
     }
+    waiting
+}
 
-    sections
+/// Drop open tasks that a line indented by `indent` can't belong to.
+fn close_shallower(open: &mut Vec<usize>, tasks: &[Task], indent: usize) {
+    while open.last().is_some_and(|&idx| tasks[idx].indent.len() >= indent) {
+        open.pop();
+    }
+}
+
+/// A scan window wide enough to include the whole section containing `line`.
+pub fn window_covering(content: &str, line: usize, scan_window: usize) -> usize {
+    let lines: Vec<&str> = content.lines().collect();
+    let start = lines
+        .iter()
+        .take(line + 1)
+        .rposition(|l| is_section_header(l).is_some())
+        .unwrap_or(0);
+    scan_window.max(lines.len().saturating_sub(start))
 }
 
 /// Find a task by ID within parsed sections.
@@ -191,71 +792,79 @@ pub fn find_task<'a>(sections: &'a [Section], id: &str) -> Result<&'a Task> {
     }
 }
 
-/// Get today's date string in DD/MM/YYYY format.
-pub fn today_str() -> String {
-    chrono::Local::now().format("%d/%m/%Y").to_string()
+/// List every `### ` header in the file as (line number, parsed date).
+/// Freeform headers are included with a `None` date.
+pub fn section_headers(content: &str, fmt: &DateFormat) -> Vec<(usize, Option<NaiveDate>)> {
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| is_section_header(line).map(|h| (i, fmt.parse(&h))))
+        .collect()
 }
 
-/// Find the first section header line number and date.
-pub fn find_first_section(content: &str) -> Option<(usize, String)> {
+/// Find the end of today's section (line number of next section or EOF).
+pub fn find_section_end(content: &str, section_line: usize) -> usize {
     let lines: Vec<&str> = content.lines().collect();
-    for (i, line) in lines.iter().enumerate() {
-        if let Some(date) = is_section_header(line) {
-            return Some((i, date));
+    for (i, line) in lines.iter().enumerate().skip(section_line + 1) {
+        if is_section_header(line).is_some() {
+            return i;
         }
     }
-    None
+    lines.len()
 }
 
-/// Find the last section header line number and date.
-pub fn find_last_section(content: &str) -> Option<(usize, String)> {
+/// Line at which a new entry should be inserted to land at the end of the
+/// section starting at `section_line`: just after its last non-blank line, so
+/// the blank separator before the next header is kept.
+pub fn find_section_insert_point(content: &str, section_line: usize) -> usize {
     let lines: Vec<&str> = content.lines().collect();
-    for (i, line) in lines.iter().enumerate().rev() {
-        if let Some(date) = is_section_header(line) {
-            return Some((i, date));
-        }
+    let mut end = find_section_end(content, section_line);
+    while end > section_line + 1 && lines[end - 1].trim().is_empty() {
+        end -= 1;
     }
-    None
+    end
 }
 
-/// Find the end of today's section (line number of next section or EOF).
-pub fn find_section_end(content: &str, section_line: usize) -> usize {
-    let lines: Vec<&str> = content.lines().collect();
-    for i in (section_line + 1)..lines.len() {
-        if is_section_header(lines[i]).is_some() {
-            return i;
-        }
-    }
-    lines.len()
+/// Find the line number of the section header for `date`. Headers are compared
+/// as dates, so "7/3/2026" and "07/03/2026" name the same section. If several
+/// headers match, the last one wins.
+pub fn find_section_for_date(content: &str, date: NaiveDate, fmt: &DateFormat) -> Option<usize> {
+    section_headers(content, fmt)
+        .into_iter()
+        .rev()
+        .find(|(_, d)| *d == Some(date))
+        .map(|(i, _)| i)
 }
 
 /// Get the raw text of today's section.
-pub fn get_today_section_text(content: &str) -> Option<String> {
-    let today = today_str();
+pub fn get_today_section_text(content: &str, fmt: &DateFormat) -> Option<String> {
     let lines: Vec<&str> = content.lines().collect();
-
-    // Find today's section header
-    let mut section_start = None;
-    for (i, line) in lines.iter().enumerate() {
-        if let Some(date) = is_section_header(line) {
-            if date == today {
-                section_start = Some(i);
-            }
-        }
-    }
-
-    let start = section_start?;
+    let start = find_section_for_date(content, dates::today(), fmt)?;
     let end = find_section_end(content, start);
     Some(lines[start..end].join("\n"))
 }
 
-/// Search tasks and notes for matching text.
+/// Search tasks and notes for matching text. Words starting with `#` or `@`
+/// narrow the results to tasks with that label or assignee; the rest of the
+/// query is matched as text. A query of only labels/assignees lists every
+/// matching task.
 pub fn search_tasks(sections: &[Section], query: &str) -> Vec<Task> {
-    let query_lower = query.to_lowercase();
+    let (filters, words): (Vec<&str>, Vec<&str>) = query
+        .split_whitespace()
+        .partition(|w| w.len() > 1 && (w.starts_with('#') || w.starts_with('@')));
+    let query_lower = words.join(" ").to_lowercase();
     let mut results = Vec::new();
 
     for sec in sections {
         for task in &sec.tasks {
+            let filtered_out = filters.iter().any(|f| match f.strip_prefix('#') {
+                Some(label) => !task.has_label(label),
+                None => !task.has_assignee(f),
+            });
+            if filtered_out {
+                continue;
+            }
+
             let title_match = task.title.to_lowercase().contains(&query_lower);
             let note_match = task
                 .notes
@@ -272,3 +881,141 @@ pub fn search_tasks(sections: &[Section], query: &str) -> Vec<Task> {
 
     results
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn parse(line: &str) -> Task {
+        parse_task_line(line, &DateFormat::new("DD/MM/YYYY").unwrap()).unwrap()
+    }
+
+    #[test]
+    fn priority_markers_map_to_levels() {
+        for (marker, level) in [
+            ("!!!", Priority::Urgent),
+            ("!p0", Priority::Urgent),
+            ("!", Priority::High),
+            ("!!", Priority::High),
+            ("!P1", Priority::High),
+            ("!p2", Priority::Med),
+            ("!p3", Priority::Low),
+        ] {
+            assert_eq!(parse(&format!("- [ ] dev-1{} x", marker)).priority, level, "{}", marker);
+        }
+        assert_eq!(parse("- [ ] dev-1 x").priority, Priority::None);
+    }
+
+    #[test]
+    fn more_bangs_never_lower_the_level() {
+        let levels: Vec<Priority> = ["", "!", "!!", "!!!"]
+            .iter()
+            .map(|m| parse(&format!("- [ ] dev-1{} x", m)).priority)
+            .collect();
+        assert!(levels.windows(2).all(|w| w[0] <= w[1]), "{:?}", levels);
+        let levels: Vec<Priority> = ["!p3", "!p2", "!p1", "!p0"]
+            .iter()
+            .map(|m| parse(&format!("- [ ] dev-1{} x", m)).priority)
+            .collect();
+        assert!(levels.windows(2).all(|w| w[0] < w[1]), "{:?}", levels);
+    }
+
+    #[test]
+    fn priority_markers_round_trip() {
+        let fmt = DateFormat::new("DD/MM/YYYY").unwrap();
+        for line in [
+            "- [ ] dev-1!!! x",
+            "- [ ] dev-1!p0 x",
+            "- [ ] dev-1!p1 x",
+            "- [ ] dev-1!! x",
+            "- [ ] dev-1 x",
+        ] {
+            assert_eq!(parse(line).render(&fmt), line);
+        }
+    }
+
+    #[test]
+    fn changing_the_level_rewrites_the_marker() {
+        let fmt = DateFormat::new("DD/MM/YYYY").unwrap();
+        let task = Task {
+            priority: Priority::Low,
+            ..parse("- [ ] dev-1!!! x")
+        };
+        assert_eq!(task.render(&fmt), "- [ ] dev-1!p3 x");
+        let task = Task {
+            priority: Priority::None,
+            ..parse("- [ ] dev-1!p0 x")
+        };
+        assert_eq!(task.render(&fmt), "- [ ] dev-1 x");
+    }
+
+    #[test]
+    fn priority_names() {
+        assert_eq!(Priority::from_name("P0"), Some(Priority::Urgent));
+        assert_eq!(Priority::from_name("urgent"), Some(Priority::Urgent));
+        assert_eq!(Priority::from_name("p1"), Some(Priority::High));
+        assert_eq!(Priority::from_name("medium"), Some(Priority::Med));
+        assert_eq!(Priority::from_name("none"), Some(Priority::None));
+        assert_eq!(Priority::from_name("critical"), None);
+    }
+
+    #[test]
+    fn labels_need_a_leading_letter() {
+        let task = parse("- [ ] dev-1 fix crash from #42 #security #p1-bug");
+        assert_eq!(task.labels, vec!["security", "p1-bug"]);
+    }
+
+    #[test]
+    fn date_markers_are_replaced_in_place() {
+        let title = "chore 📅 2026-10-15 ⏳ 2026-10-13 every:1w";
+        let title = with_due(title, Some(date(2026, 10, 22)));
+        let title = with_scheduled(&title, Some(date(2026, 10, 20)));
+        assert_eq!(title, "chore 📅 2026-10-22 ⏳ 2026-10-20 every:1w");
+    }
+
+    #[test]
+    fn date_markers_are_added_and_removed() {
+        assert_eq!(with_scheduled("plan", Some(date(2026, 10, 19))), "plan scheduled:2026-10-19");
+        assert_eq!(with_due("ship due:2026-10-15 now", None), "ship now");
+        assert_eq!(with_due("ship", None), "ship");
+    }
+
+    #[test]
+    fn blocker_tree_walks_both_kinds_of_link() {
+        let tasks = [
+            parse("- [ ] dev-1 ship blocked-by:dev-2"),
+            parse("- [ ] dev-2 review"),
+            parse("- [x] dev-3 spec blocks:dev-2"),
+            parse("- [ ] dev-4 loop blocked-by:dev-1 blocks:dev-1"),
+        ];
+        let refs: Vec<&Task> = tasks.iter().collect();
+        let tree: Vec<(usize, String)> = blocker_tree(&refs, &tasks[0])
+            .into_iter()
+            .map(|(depth, t)| (depth, t.id()))
+            .collect();
+        assert_eq!(tree, [(1, "dev-2".into()), (2, "dev-3".into()), (1, "dev-4".into())]);
+        let deps: Vec<String> = dependents(&refs, &tasks[1]).iter().map(|t| t.id()).collect();
+        assert_eq!(deps, ["dev-1"]);
+    }
+
+    #[test]
+    fn waiting_ids_match_open_blockers() {
+        let tasks = [
+            parse("- [ ] dev-1 ship blocked-by:dev-2"),
+            parse("- [ ] dev-2 review"),
+            parse("- [x] dev-3 spec blocks:dev-2"),
+            parse("- [ ] dev-4 docs blocks:dev-5"),
+            parse("- [ ] dev-5 publish blocked-by:dev-9"),
+        ];
+        let refs: Vec<&Task> = tasks.iter().collect();
+        let waiting = waiting_ids(&refs);
+        for t in &tasks {
+            assert_eq!(waiting.contains(&t.id()), !open_blockers(&refs, t).is_empty(), "{}", t.id());
+        }
+        assert_eq!(waiting.len(), 2);
+    }
+}
@@ -0,0 +1,117 @@
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+
+/// How often a task comes back, from an `every:<rule>` marker in its title.
+///
+/// Rules: `<n>d`, `<n>w`, `<n>m`, `<n>y` (n defaults to 1, so `every:w` works),
+/// the aliases `day`, `week`, `month`, `year`, or a weekday name such as
+/// `monday` / `mon`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Recurrence {
+    Days(u32),
+    Weeks(u32),
+    Months(u32),
+    Years(u32),
+    Weekday(Weekday),
+}
+
+impl Recurrence {
+    pub fn parse(rule: &str) -> Option<Self> {
+        let rule = rule.to_lowercase();
+        match rule.as_str() {
+            "day" | "daily" => return Some(Self::Days(1)),
+            "week" | "weekly" => return Some(Self::Weeks(1)),
+            "month" | "monthly" => return Some(Self::Months(1)),
+            "year" | "yearly" => return Some(Self::Years(1)),
+            _ => {}
+        }
+        if let Ok(weekday) = rule.parse::<Weekday>() {
+            return Some(Self::Weekday(weekday));
+        }
+
+        let unit = rule.chars().last()?;
+        let count = &rule[..rule.len() - unit.len_utf8()];
+        let n: u32 = if count.is_empty() { 1 } else { count.parse().ok()? };
+        if n == 0 {
+            return None;
+        }
+        match unit {
+            'd' => Some(Self::Days(n)),
+            'w' => Some(Self::Weeks(n)),
+            'm' => Some(Self::Months(n)),
+            'y' => Some(Self::Years(n)),
+            _ => None,
+        }
+    }
+
+    /// The first occurrence strictly after `date`.
+    pub fn next_after(&self, date: NaiveDate) -> NaiveDate {
+        let next = match *self {
+            Self::Days(n) => date.checked_add_days(Days::new(n.into())),
+            Self::Weeks(n) => date.checked_add_days(Days::new(u64::from(n) * 7)),
+            Self::Months(n) => date.checked_add_months(Months::new(n)),
+            Self::Years(n) => date.checked_add_months(Months::new(n.saturating_mul(12))),
+            Self::Weekday(weekday) => {
+                let ahead = (weekday.num_days_from_monday() + 7
+                    - date.weekday().num_days_from_monday())
+                    % 7;
+                let ahead = if ahead == 0 { 7 } else { ahead };
+                date.checked_add_days(Days::new(ahead.into()))
+            }
+        };
+        next.unwrap_or(date)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn parses_counted_rules() {
+        assert_eq!(Recurrence::parse("3d"), Some(Recurrence::Days(3)));
+        assert_eq!(Recurrence::parse("2W"), Some(Recurrence::Weeks(2)));
+        assert_eq!(Recurrence::parse("m"), Some(Recurrence::Months(1)));
+        assert_eq!(Recurrence::parse("1y"), Some(Recurrence::Years(1)));
+    }
+
+    #[test]
+    fn parses_aliases_and_weekdays() {
+        assert_eq!(Recurrence::parse("week"), Some(Recurrence::Weeks(1)));
+        assert_eq!(Recurrence::parse("daily"), Some(Recurrence::Days(1)));
+        assert_eq!(Recurrence::parse("monday"), Some(Recurrence::Weekday(Weekday::Mon)));
+        assert_eq!(Recurrence::parse("Fri"), Some(Recurrence::Weekday(Weekday::Fri)));
+    }
+
+    #[test]
+    fn rejects_bad_rules() {
+        assert_eq!(Recurrence::parse("0d"), None);
+        assert_eq!(Recurrence::parse("3x"), None);
+        assert_eq!(Recurrence::parse("d3"), None);
+        assert_eq!(Recurrence::parse(""), None);
+    }
+
+    #[test]
+    fn steps_by_days_and_weeks() {
+        assert_eq!(Recurrence::Days(3).next_after(date(2026, 10, 30)), date(2026, 11, 2));
+        assert_eq!(Recurrence::Weeks(2).next_after(date(2026, 10, 17)), date(2026, 10, 31));
+    }
+
+    #[test]
+    fn months_clamp_to_the_end_of_short_months() {
+        assert_eq!(Recurrence::Months(1).next_after(date(2026, 1, 31)), date(2026, 2, 28));
+        assert_eq!(Recurrence::Years(1).next_after(date(2028, 2, 29)), date(2029, 2, 28));
+    }
+
+    #[test]
+    fn weekday_is_strictly_after() {
+        // 2026-10-17 is a Saturday
+        let monday = Recurrence::Weekday(Weekday::Mon);
+        assert_eq!(monday.next_after(date(2026, 10, 17)), date(2026, 10, 19));
+        let saturday = Recurrence::Weekday(Weekday::Sat);
+        assert_eq!(saturday.next_after(date(2026, 10, 17)), date(2026, 10, 24));
+    }
+}
@@ -1,7 +1,7 @@
 use crate::config::{Config, FileEntry, FileMode};
 use crate::error::{Result, TlError};
-use crate::parser;
-use std::path::PathBuf;
+use crate::index::TaskIndex;
+use std::path::{Path, PathBuf};
 
 /// Result of resolving which file a tag should go to.
 #[derive(Debug)]
@@ -61,29 +61,79 @@ pub fn resolve_file_for_tag(config: &Config, tag: &str) -> Result<RouteResult> {
     }
 }
 
-/// Find which file contains a given task ID by scanning all files.
-/// Used for operations on existing tasks (done, undo, note, edit, delete).
-pub fn find_file_for_task(config: &Config, task_id: &str) -> Result<PathBuf> {
-    let paths = config.all_file_paths();
+/// Pick the file a new task with `tag` goes to. An explicit `label` must name
+/// one of the files `resolve_file_for_tag` allows; without one, an ambiguous
+/// route defaults to the first variable file.
+pub fn file_for_new_task(config: &Config, tag: &str, label: Option<&str>) -> Result<PathBuf> {
+    let route = resolve_file_for_tag(config, tag)?;
+    let Some(label) = label else {
+        return Ok(match route {
+            RouteResult::Resolved(p) => p,
+            RouteResult::Ambiguous(files) => files[0].resolved_path(),
+        });
+    };
 
-    for path in &paths {
-        if !path.exists() {
+    let path = config
+        .effective_files()
+        .iter()
+        .find(|f| f.label == label)
+        .map(|f| f.resolved_path())
+        .ok_or_else(|| TlError::Config(format!("no log file labelled '{}'", label)))?;
+    let allowed = match &route {
+        RouteResult::Resolved(p) => *p == path,
+        RouteResult::Ambiguous(files) => files.iter().any(|f| f.resolved_path() == path),
+    };
+    if !allowed {
+        // Explains the fixed-file cases
+        check_tag_for_file(config, tag, &path)?;
+        return Err(TlError::Config(format!(
+            "tag '{}' can't be added to file '{}'",
+            tag, label
+        )));
+    }
+    Ok(path)
+}
+
+/// Check that a task with `tag` may live in the file at `path`: a fixed file
+/// only takes its own tags, and a tag claimed by a fixed file can't go anywhere
+/// else.
+pub fn check_tag_for_file(config: &Config, tag: &str, path: &Path) -> Result<()> {
+    for f in config.effective_files() {
+        if f.mode != FileMode::Fixed {
             continue;
         }
-        let content = std::fs::read_to_string(path)?;
-        let sections = parser::parse_log(&content, config.scan_window_lines);
-        if parser::find_task(&sections, task_id).is_ok() {
-            return Ok(path.clone());
+        let here = f.resolved_path() == path;
+        let claims = f.tags.iter().any(|t| t == tag);
+        if here && !claims {
+            return Err(TlError::Config(format!(
+                "file '{}' only accepts tags: {}",
+                f.label,
+                f.tags.join(", ")
+            )));
+        }
+        if !here && claims {
+            return Err(TlError::Config(format!(
+                "tag '{}' belongs to fixed file '{}'",
+                tag, f.label
+            )));
         }
     }
+    Ok(())
+}
 
-    Err(TlError::TaskNotFound(task_id.to_string()))
+/// Find which file contains a given task ID, and the task's line in it.
+/// Used for operations on existing tasks (done, undo, note, edit, delete).
+/// Backed by the full-history index, so tasks outside the scan window are
+/// found too.
+pub fn find_file_for_task(config: &Config, task_id: &str) -> Result<(PathBuf, usize)> {
+    let index = TaskIndex::open(config)?;
+    let (path, entry) = index.find(task_id)?;
+    Ok((path, entry.line))
 }
 
-/// Get eligible files for adding a new tag. Returns the variable files plus
-/// any fixed file that claims this tag. Used by the TUI to build the file
-/// picker list.
-#[allow(dead_code)]
+/// Get eligible files for a tag. Returns the variable files plus any fixed
+/// file that claims this tag. Used by the TUI to build the file picker for
+/// moving a task.
 pub fn eligible_files_for_tag(config: &Config, tag: &str) -> Vec<FileEntry> {
     let files = config.effective_files();
     let mut eligible = Vec::new();
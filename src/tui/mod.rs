@@ -1,6 +1,7 @@
 use crate::config::{Config, FileEntry};
+use crate::dates::{self, DateFormat};
 use crate::error::{Result, TlError};
-use crate::parser::{self, Task};
+use crate::parser::{self, Priority, Status, Task};
 use crate::router;
 use crate::writer;
 
@@ -12,7 +13,7 @@ use crossterm::ExecutableCommand;
 use ratatui::prelude::*;
 use ratatui::widgets::*;
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashSet};
 use std::io::stdout;
 use std::time::Duration;
 
@@ -29,6 +30,7 @@ enum Mode {
     AddTag,
     AddTitle,
     PickFile,
+    MoveFile,
     NoteInput,
     Search,
     RenameTag,
@@ -55,12 +57,23 @@ struct App {
     mode: Mode,
     input: String,
     add_tag: String,
+    /// Task being moved while the file picker is open (`m`)
+    move_id: String,
     status_msg: String,
     search_query: String,
+    /// Only show tasks with this `#label` (cycled with `#`)
+    label_filter: Option<String>,
+    /// Hide open tasks that still have open blockers (toggled with `v`)
+    ready_only: bool,
+    /// IDs of tasks with an open blocker, rebuilt on each refresh
+    waiting: HashSet<String>,
     show_detail: bool,
     detail_scroll: u16,
     detail_note_idx: Option<usize>,
     detail_links: Vec<String>,
+    /// Tasks mentioning the detail task; `n`/`f` cycle through them after
+    /// `detail_links`
+    detail_refs: Vec<String>,
     detail_link_idx: usize,
     should_quit: bool,
     hide_empty_projects: bool,
@@ -75,6 +88,8 @@ struct App {
     pick_file_idx: usize,
     /// Multi-file: map tag -> file label (built during refresh)
     tag_file_labels: std::collections::HashMap<String, String>,
+    /// Configured date format, for rendering timestamps
+    date_fmt: DateFormat,
 }
 
 impl App {
@@ -91,12 +106,17 @@ impl App {
             mode: Mode::Normal,
             input: String::new(),
             add_tag: String::new(),
+            move_id: String::new(),
             status_msg: String::from("? for help | Tab to switch panels"),
             search_query: String::new(),
+            label_filter: None,
+            ready_only: false,
+            waiting: HashSet::new(),
             show_detail: false,
             detail_scroll: 0,
             detail_note_idx: None,
             detail_links: Vec::new(),
+            detail_refs: Vec::new(),
             detail_link_idx: 0,
             should_quit: false,
             hide_empty_projects: config.hide_empty_projects,
@@ -106,6 +126,7 @@ impl App {
             pick_file_options: Vec::new(),
             pick_file_idx: 0,
             tag_file_labels: std::collections::HashMap::new(),
+            date_fmt: config.date_fmt()?,
         };
         app.refresh()?;
         Ok(app)
@@ -114,15 +135,19 @@ impl App {
     fn refresh(&mut self) -> Result<()> {
         let config = Config::load()?;
         self.file_entries = config.effective_files();
+        self.date_fmt = config.date_fmt()?;
 
         if self.search_query.is_empty() {
             self.all_tasks = writer::all_tasks()?;
         } else {
             self.all_tasks = writer::search(&self.search_query)?;
         }
+        let all: Vec<&Task> = self.all_tasks.iter().collect();
+        self.waiting = parser::waiting_ids(&all);
 
         // Build tag -> file label mapping
         self.tag_file_labels.clear();
+        let fmt = config.date_fmt()?;
         let multi_file = self.file_entries.len() > 1;
         if multi_file {
             for entry in &self.file_entries {
@@ -131,7 +156,7 @@ impl App {
                     continue;
                 }
                 if let Ok(content) = std::fs::read_to_string(&path) {
-                    let sections = parser::parse_log(&content, config.scan_window_lines);
+                    let sections = parser::parse_log(&content, config.scan_window_lines, &fmt);
                     for sec in &sections {
                         for task in &sec.tasks {
                             self.tag_file_labels
@@ -159,7 +184,11 @@ impl App {
         if self.hide_empty_projects {
             self.projects
                 .iter()
-                .filter(|tag| self.all_tasks.iter().any(|t| t.tag == **tag && !t.done))
+                .filter(|tag| {
+                    self.all_tasks
+                        .iter()
+                        .any(|t| t.tag == **tag && !t.status.is_closed())
+                })
                 .collect()
         } else {
             self.projects.iter().collect()
@@ -173,18 +202,128 @@ impl App {
         }
         let idx = self.project_idx.min(visible.len().saturating_sub(1));
         let tag = visible[idx];
-        self.all_tasks.iter().filter(|t| t.tag == *tag).collect()
+        self.all_tasks
+            .iter()
+            .filter(|t| t.tag == *tag)
+            .filter(|t| self.label_filter.as_ref().is_none_or(|l| t.has_label(l)))
+            .collect()
+    }
+
+    /// Step the selected task's priority: none -> high -> med -> low -> none.
+    fn cycle_priority(&mut self) -> Result<()> {
+        if let Some(task) = self.selected_task() {
+            let id = task.id();
+            let next = task.priority.cycle();
+            match writer::set_priority(&id, next) {
+                Ok(()) => {
+                    self.status_msg = match next {
+                        Priority::None => format!("{} marked normal priority", id),
+                        p => format!("{} marked {} priority", id, p.name().to_uppercase()),
+                    };
+                    self.refresh()?;
+                }
+                Err(e) => self.status_msg = format!("Error: {}", e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Put the selected task into `status`, or back to open if it's already
+    /// there.
+    fn toggle_status(&mut self, status: Status) -> Result<()> {
+        if let Some(task) = self.selected_task() {
+            let id = task.id();
+            let next = if task.status == status { Status::Open } else { status };
+            match writer::set_status(&id, next) {
+                Ok(()) => {
+                    self.status_msg = format!("{} is now {}", id, next.name());
+                    self.refresh()?;
+                }
+                Err(e) => self.status_msg = format!("Error: {}", e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Every label used by any loaded task, sorted.
+    fn labels(&self) -> Vec<String> {
+        let labels: BTreeSet<String> = self
+            .all_tasks
+            .iter()
+            .flat_map(|t| t.labels.iter().map(|l| l.to_lowercase()))
+            .collect();
+        labels.into_iter().collect()
+    }
+
+    /// Move the label filter to the next label, wrapping back to no filter.
+    fn cycle_label_filter(&mut self) {
+        let labels = self.labels();
+        let next = match &self.label_filter {
+            None => labels.first(),
+            Some(current) => labels
+                .iter()
+                .position(|l| l == current)
+                .and_then(|i| labels.get(i + 1)),
+        };
+        self.label_filter = next.cloned();
+        self.status_msg = match &self.label_filter {
+            Some(label) => format!("Showing #{} (# for next label, c to clear)", label),
+            None if labels.is_empty() => "No #labels in loaded tasks".to_string(),
+            None => "Showing all labels".to_string(),
+        };
+        self.task_idx = 0;
+        self.completed_idx = 0;
+        self.clamp_task_idx();
     }
 
     fn open_tasks(&self) -> Vec<&Task> {
-        let mut tasks: Vec<&Task> = self.filtered_tasks().into_iter().filter(|t| !t.done).collect();
-        // Sort: priority tasks first
-        tasks.sort_by(|a, b| b.priority.cmp(&a.priority));
-        tasks
+        let mut tasks: Vec<&Task> = self
+            .filtered_tasks()
+            .into_iter()
+            .filter(|t| !t.status.is_closed())
+            .filter(|t| !self.ready_only || !self.waiting.contains(&t.id()))
+            .collect();
+        // Sort: priority tasks first, then oldest first so nothing gets buried
+        tasks.sort_by_key(|t| (std::cmp::Reverse(t.priority), t.section_date));
+
+        // Tree order: subtasks follow their parent. A subtask whose parent
+        // isn't listed (done, or in another project) is shown at top level.
+        let listed: Vec<String> = tasks.iter().map(|t| t.id()).collect();
+        let mut ordered = Vec::with_capacity(tasks.len());
+        for root in tasks
+            .iter()
+            .filter(|t| !t.parent.as_ref().is_some_and(|p| listed.contains(p)))
+        {
+            push_subtree(root, &tasks, &mut ordered);
+        }
+        ordered
+    }
+
+    /// How many listed ancestors `task` has in `tasks`.
+    fn task_depth(task: &Task, tasks: &[&Task]) -> usize {
+        let mut depth = 0;
+        let mut parent = task.parent.clone();
+        while let Some(p) = parent {
+            match tasks.iter().find(|t| t.id() == p) {
+                Some(t) if depth < tasks.len() => {
+                    depth += 1;
+                    parent = t.parent.clone();
+                }
+                _ => break,
+            }
+        }
+        depth
     }
 
     fn completed_tasks(&self) -> Vec<&Task> {
-        self.filtered_tasks().into_iter().filter(|t| t.done).collect()
+        let mut tasks: Vec<&Task> = self
+            .filtered_tasks()
+            .into_iter()
+            .filter(|t| t.status.is_closed())
+            .collect();
+        // Most recently completed sections first
+        tasks.sort_by_key(|t| std::cmp::Reverse(t.section_date));
+        tasks
     }
 
     fn clamp_task_idx(&mut self) {
@@ -229,32 +368,43 @@ impl App {
 
     /// Extract all task-ID links from the currently selected task's notes and title.
     fn extract_task_links(&self, task: &Task) -> Vec<String> {
-        let mut links = Vec::new();
-        let task_id = task.id();
-
-        // From title
-        for link in parser::extract_links(&task.title) {
-            if link != task_id && !links.contains(&link) {
-                links.push(link);
-            }
-        }
+        parser::task_links(task)
+    }
 
-        // From notes
-        for note in &task.notes {
-            for link in parser::extract_links(&note.text) {
-                if link != task_id && !links.contains(&link) {
-                    links.push(link);
-                }
+    /// Load forward links and backlinks for the selected task's detail popup.
+    fn load_detail_links(&mut self) {
+        let Some(task) = self.selected_task() else {
+            return;
+        };
+        let id = task.id();
+        self.detail_links = self.extract_task_links(task);
+        self.detail_refs.clear();
+        for r in writer::task_references(&id).unwrap_or_default() {
+            if !self.detail_refs.contains(&r.from) {
+                self.detail_refs.push(r.from);
             }
         }
+        self.detail_link_idx = 0;
+    }
 
-        links
+    /// Links then backlinks, in the order `n` cycles through them.
+    fn detail_targets(&self) -> Vec<String> {
+        self.detail_links.iter().chain(&self.detail_refs).cloned().collect()
     }
 
     /// Jump to a task by its ID. Pushes current position onto nav stack.
     fn jump_to_task(&mut self, target_id: &str) -> Result<bool> {
-        // Find which project/tag the target belongs to
-        let target_task = self.all_tasks.iter().find(|t| t.id() == target_id);
+        // Find which project/tag the target belongs to, following retag
+        // aliases ("was dev-4") for old IDs
+        let target_task = self
+            .all_tasks
+            .iter()
+            .find(|t| t.id() == target_id)
+            .or_else(|| {
+                self.all_tasks
+                    .iter()
+                    .find(|t| t.aliases().iter().any(|a| a == target_id))
+            });
         let target_task = match target_task {
             Some(t) => t.clone(),
             None => {
@@ -262,6 +412,7 @@ impl App {
                 return Ok(false);
             }
         };
+        let target_id = target_task.id();
 
         let visible = self.visible_projects();
         let target_project_idx = visible.iter().position(|p| **p == target_task.tag);
@@ -286,7 +437,7 @@ impl App {
         self.completed_idx = 0;
 
         // Find the task index in open or completed
-        if target_task.done {
+        if target_task.status.is_closed() {
             self.focus = Focus::Completed;
             let completed = self.completed_tasks();
             if let Some(idx) = completed.iter().position(|t| t.id() == target_id) {
@@ -304,10 +455,7 @@ impl App {
         self.show_detail = true;
         self.detail_scroll = 0;
         self.detail_note_idx = None;
-        if let Some(task) = self.selected_task() {
-            self.detail_links = self.extract_task_links(task);
-            self.detail_link_idx = 0;
-        }
+        self.load_detail_links();
         self.status_msg = format!("Jumped to {}", target_id);
         Ok(true)
     }
@@ -330,6 +478,7 @@ impl App {
             Mode::AddTag => self.handle_add_tag_key(key),
             Mode::AddTitle => self.handle_add_title_key(key),
             Mode::PickFile => self.handle_pick_file_key(key),
+            Mode::MoveFile => self.handle_move_file_key(key),
             Mode::NoteInput => self.handle_note_input_key(key),
             Mode::Search => self.handle_search_key(key),
             Mode::RenameTag => self.handle_rename_tag_key(key),
@@ -353,14 +502,11 @@ impl App {
 
         match key.code {
             KeyCode::Char('q') | KeyCode::Esc => self.should_quit = true,
-            KeyCode::Enter => {
-                if let Some(task) = self.selected_task() {
-                    self.detail_links = self.extract_task_links(task);
-                    self.detail_link_idx = 0;
-                    self.show_detail = true;
-                    self.detail_scroll = 0;
-                    self.detail_note_idx = None;
-                }
+            KeyCode::Enter if self.selected_task().is_some() => {
+                self.load_detail_links();
+                self.show_detail = true;
+                self.detail_scroll = 0;
+                self.detail_note_idx = None;
             }
             KeyCode::Tab => {
                 self.focus = match self.focus {
@@ -484,8 +630,22 @@ impl App {
                 if let Some(task) = self.selected_task() {
                     let id = task.id();
                     match writer::complete_task(&id) {
-                        Ok(()) => {
-                            self.status_msg = format!("Completed {}", id);
+                        Ok(completion) => {
+                            self.status_msg = match completion.recurred {
+                                Some((next, date)) => format!(
+                                    "Completed {}, recurs as {} on {}",
+                                    id,
+                                    next,
+                                    self.date_fmt.format(date)
+                                ),
+                                None => format!("Completed {}", id),
+                            };
+                            if !completion.open_blockers.is_empty() {
+                                self.status_msg.push_str(&format!(
+                                    " (warning: still blocked by {})",
+                                    completion.open_blockers.join(", ")
+                                ));
+                            }
                             self.refresh()?;
                         }
                         Err(e) => self.status_msg = format!("Error: {}", e),
@@ -509,30 +669,15 @@ impl App {
                     self.status_msg = "u: select a completed task first (Tab to Completed panel)".to_string();
                 }
             }
-            KeyCode::Char('n') => {
-                if self.selected_task().is_some() {
-                    self.mode = Mode::NoteInput;
-                    self.input.clear();
-                    self.status_msg = "Enter note text:".to_string();
-                }
-            }
-            KeyCode::Char('p') => {
-                // Toggle priority
-                if let Some(task) = self.selected_task() {
-                    let id = task.id();
-                    match writer::toggle_priority(&id) {
-                        Ok(new_p) => {
-                            self.status_msg = if new_p {
-                                format!("{} marked HIGH priority", id)
-                            } else {
-                                format!("{} marked normal priority", id)
-                            };
-                            self.refresh()?;
-                        }
-                        Err(e) => self.status_msg = format!("Error: {}", e),
-                    }
-                }
+            KeyCode::Char('n') if self.selected_task().is_some() => {
+                self.mode = Mode::NoteInput;
+                self.input.clear();
+                self.status_msg = "Enter note text:".to_string();
             }
+            KeyCode::Char('p') => self.cycle_priority()?,
+            KeyCode::Char('s') => self.toggle_status(Status::InProgress)?,
+            KeyCode::Char('B') => self.toggle_status(Status::Blocked)?,
+            KeyCode::Char('C') => self.toggle_status(Status::Cancelled)?,
             KeyCode::Char('e') => {
                 // Edit task title
                 if let Some(task) = self.selected_task() {
@@ -544,6 +689,18 @@ impl App {
                     self.status_msg = "Edit title (Enter to save, Esc to cancel):".to_string();
                 }
             }
+            KeyCode::Char('m') => {
+                // Move task to another file
+                if let Some(task) = self.selected_task() {
+                    let id = task.id();
+                    let config = Config::load()?;
+                    self.pick_file_options = router::eligible_files_for_tag(&config, &task.tag);
+                    self.pick_file_idx = 0;
+                    self.move_id = id.clone();
+                    self.mode = Mode::MoveFile;
+                    self.status_msg = format!("Move {} to (j/k, Enter to confirm):", id);
+                }
+            }
             KeyCode::Char('x') => {
                 // Delete task
                 if let Some(task) = self.selected_task() {
@@ -571,8 +728,19 @@ impl App {
                 self.input.clear();
                 self.status_msg = "Search:".to_string();
             }
+            KeyCode::Char('#') => self.cycle_label_filter(),
+            KeyCode::Char('v') => {
+                self.ready_only = !self.ready_only;
+                self.status_msg = if self.ready_only {
+                    "Showing only tasks with no open blockers (v to show all)".to_string()
+                } else {
+                    "Showing all open tasks (v for ready only)".to_string()
+                };
+                self.clamp_task_idx();
+            }
             KeyCode::Char('c') => {
                 self.search_query.clear();
+                self.label_filter = None;
                 self.status_msg = "Filter cleared".to_string();
                 self.refresh()?;
             }
@@ -595,7 +763,7 @@ impl App {
             }
             KeyCode::Char('?') => {
                 self.status_msg =
-                    "j/k:nav h/l:panel a:add e:edit x:del d:done u:undo n:note p:priority R:rename /:search q:quit"
+                    "j/k:nav h/l:panel a:add e:edit x:del d:done u:undo n:note p:priority s:start B:block C:cancel R:rename /:search #:label v:ready m:move q:quit"
                         .to_string();
             }
             _ => {}
@@ -676,44 +844,24 @@ impl App {
                     self.status_msg = "Edit title (Enter to save, Esc to cancel):".to_string();
                 }
             }
-            KeyCode::Char('f') => {
-                // Follow a link
-                if !self.detail_links.is_empty() {
-                    let link = self.detail_links[self.detail_link_idx].clone();
-                    self.show_detail = false;
-                    self.jump_to_task(&link)?;
-                }
+            KeyCode::Char('f') if !self.detail_targets().is_empty() => {
+                // Follow a link or backlink
+                let link = self.detail_targets()[self.detail_link_idx].clone();
+                self.show_detail = false;
+                self.jump_to_task(&link)?;
             }
-            KeyCode::Char('n') => {
+            KeyCode::Char('n') if !self.detail_targets().is_empty() => {
                 // Cycle to next link
-                if !self.detail_links.is_empty() {
-                    self.detail_link_idx =
-                        (self.detail_link_idx + 1) % self.detail_links.len();
-                    self.status_msg = format!(
-                        "Link [{}/{}]: {}",
-                        self.detail_link_idx + 1,
-                        self.detail_links.len(),
-                        self.detail_links[self.detail_link_idx]
-                    );
-                }
-            }
-            KeyCode::Char('p') => {
-                // Toggle priority from detail view
-                if let Some(task) = self.selected_task() {
-                    let id = task.id();
-                    match writer::toggle_priority(&id) {
-                        Ok(new_p) => {
-                            self.status_msg = if new_p {
-                                format!("{} marked HIGH priority", id)
-                            } else {
-                                format!("{} marked normal priority", id)
-                            };
-                            self.refresh()?;
-                        }
-                        Err(e) => self.status_msg = format!("Error: {}", e),
-                    }
-                }
+                let targets = self.detail_targets();
+                self.detail_link_idx = (self.detail_link_idx + 1) % targets.len();
+                self.status_msg = format!(
+                    "Link [{}/{}]: {}",
+                    self.detail_link_idx + 1,
+                    targets.len(),
+                    targets[self.detail_link_idx]
+                );
             }
+            KeyCode::Char('p') => self.cycle_priority()?,
             KeyCode::Char('b') => {
                 // Back from link jump
                 self.nav_back();
@@ -786,7 +934,7 @@ impl App {
                     self.status_msg = "Title cannot be empty".to_string();
                 } else {
                     let result = if let Some(ref path) = self.pending_file {
-                        writer::add_task_to_file(&self.add_tag, &self.input, false, path)
+                        writer::add_task_to_file(&self.add_tag, &self.input, Priority::None, path)
                     } else {
                         writer::add_task(&self.add_tag, &self.input)
                     };
@@ -823,11 +971,8 @@ impl App {
                 self.pending_file = None;
                 self.status_msg = "Cancelled".to_string();
             }
-            KeyCode::Char('j') | KeyCode::Down => {
-                if !self.pick_file_options.is_empty() {
-                    self.pick_file_idx =
-                        (self.pick_file_idx + 1).min(self.pick_file_options.len() - 1);
-                }
+            KeyCode::Char('j') | KeyCode::Down if !self.pick_file_options.is_empty() => {
+                self.pick_file_idx = (self.pick_file_idx + 1).min(self.pick_file_options.len() - 1);
             }
             KeyCode::Char('k') | KeyCode::Up => {
                 self.pick_file_idx = self.pick_file_idx.saturating_sub(1);
@@ -848,6 +993,34 @@ impl App {
         Ok(())
     }
 
+    fn handle_move_file_key(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Esc => {
+                self.mode = Mode::Normal;
+                self.status_msg = "Cancelled".to_string();
+            }
+            KeyCode::Char('j') | KeyCode::Down if !self.pick_file_options.is_empty() => {
+                self.pick_file_idx = (self.pick_file_idx + 1).min(self.pick_file_options.len() - 1);
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.pick_file_idx = self.pick_file_idx.saturating_sub(1);
+            }
+            KeyCode::Enter => {
+                if let Some(entry) = self.pick_file_options.get(self.pick_file_idx) {
+                    let id = self.move_id.clone();
+                    self.status_msg = match writer::move_task(&id, Some(&entry.label), None) {
+                        Ok((label, _)) => format!("Moved {} to {}", id, label),
+                        Err(e) => format!("Error: {}", e),
+                    };
+                    self.mode = Mode::Normal;
+                    self.refresh()?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
     fn handle_note_input_key(&mut self, key: KeyEvent) -> Result<()> {
         match key.code {
             KeyCode::Esc => {
@@ -860,7 +1033,7 @@ impl App {
                     self.status_msg = "Note cannot be empty".to_string();
                 } else if let Some(task) = self.selected_task() {
                     let id = task.id();
-                    match writer::add_note(&id, &self.input) {
+                    match writer::add_note(&id, &self.input, Some("tui")) {
                         Ok(()) => {
                             self.status_msg = format!("Note added to {}", id);
                             self.mode = Mode::Normal;
@@ -931,9 +1104,14 @@ impl App {
                 } else {
                     let old = self.add_tag.clone();
                     let new = self.input.clone();
-                    match writer::rename_tag(&old, &new) {
-                        Ok(()) => {
-                            self.status_msg = format!("Renamed {} -> {}", old, new);
+                    match writer::rename_tag(&old, &new, false) {
+                        Ok(changes) => {
+                            self.status_msg = format!(
+                                "Renamed {} -> {} ({} lines updated)",
+                                old,
+                                new,
+                                changes.len()
+                            );
                             self.mode = Mode::Normal;
                             self.refresh()?;
                         }
@@ -1044,6 +1222,29 @@ impl App {
     }
 }
 
+/// Colour for a priority level in task lists, None for no priority.
+fn priority_color(priority: Priority) -> Option<Color> {
+    match priority {
+        Priority::Urgent => Some(Color::LightRed),
+        Priority::High => Some(Color::Red),
+        Priority::Med => Some(Color::LightMagenta),
+        Priority::Low => Some(Color::Blue),
+        Priority::None => None,
+    }
+}
+
+/// Append `task` and, depth-first, its subtasks from `tasks`.
+fn push_subtree<'a>(task: &'a Task, tasks: &[&'a Task], out: &mut Vec<&'a Task>) {
+    if out.iter().any(|t| std::ptr::eq(*t, task)) {
+        return;
+    }
+    out.push(task);
+    let id = task.id();
+    for child in tasks.iter().filter(|t| t.parent.as_deref() == Some(id.as_str())) {
+        push_subtree(child, tasks, out);
+    }
+}
+
 /// Truncate a string to fit within `max_width` chars, appending "…" if truncated.
 fn truncate(s: &str, max_width: usize) -> String {
     if max_width == 0 {
@@ -1120,7 +1321,7 @@ fn ui(frame: &mut Frame, app: &App) {
             let open_count = app
                 .all_tasks
                 .iter()
-                .filter(|t| t.tag == **tag && !t.done)
+                .filter(|t| t.tag == **tag && !t.status.is_closed())
                 .count();
             let label = truncate(
                 &format!("{} ({}/{})", tag, open_count, task_count),
@@ -1155,6 +1356,7 @@ fn ui(frame: &mut Frame, app: &App) {
         Color::DarkGray
     };
     let open = app.open_tasks();
+    let today = dates::today();
     let open_items: Vec<ListItem> = open
         .iter()
         .enumerate()
@@ -1164,34 +1366,53 @@ fn ui(frame: &mut Frame, app: &App) {
             } else {
                 format!(" [{}]", task.notes.len())
             };
-            let priority_marker = if task.priority { "! " } else { "" };
+            let priority_marker = match task.priority {
+                Priority::None => String::new(),
+                _ => format!("{} ", task.written_priority()),
+            };
+            let tree = match App::task_depth(task, &open) {
+                0 => String::new(),
+                depth => format!("{}└ ", "  ".repeat(depth - 1)),
+            };
+            let rollup = parser::subtask_progress(&app.all_tasks, &task.id())
+                .map(|(done, total)| format!(" ({}/{} subtasks)", done, total))
+                .unwrap_or_default();
             let label = truncate(
                 &format!(
-                    "{}[ ] {} {}{}",
+                    "{}{}[{}] {} {}{}{}",
+                    tree,
                     priority_marker,
+                    task.status.marker(),
                     task.id(),
                     task.title,
+                    rollup,
                     note_hint
                 ),
                 open_width,
             );
-            let style = if i == app.task_idx && app.focus == Focus::Tasks {
-                if task.priority {
-                    Style::default().bg(Color::DarkGray).fg(Color::Red)
-                } else {
-                    Style::default().bg(Color::DarkGray).fg(Color::White)
-                }
-            } else if i == app.task_idx {
-                if task.priority {
-                    Style::default().fg(Color::Red)
-                } else {
-                    Style::default().fg(Color::White)
-                }
-            } else if task.priority {
-                Style::default().fg(Color::Red)
-            } else {
-                Style::default()
+            let overdue = task.is_overdue(today);
+            // Priority colour wins, then state; overdue tasks without either are yellow
+            let fg = priority_color(task.priority)
+                .or(match task.status {
+                    Status::InProgress => Some(Color::Cyan),
+                    Status::Blocked => Some(Color::DarkGray),
+                    _ => None,
+                })
+                .or(overdue.then_some(Color::Yellow));
+            let mut style = match fg {
+                Some(color) => Style::default().fg(color),
+                None if i == app.task_idx => Style::default().fg(Color::White),
+                None => Style::default(),
             };
+            if i == app.task_idx && app.focus == Focus::Tasks {
+                style = style.bg(Color::DarkGray);
+            }
+            if overdue {
+                style = style.add_modifier(Modifier::BOLD);
+            }
+            if task.status == Status::Blocked {
+                style = style.add_modifier(Modifier::ITALIC);
+            }
             ListItem::new(label).style(style)
         })
         .collect();
@@ -1208,6 +1429,15 @@ fn ui(frame: &mut Frame, app: &App) {
             format!(" Open — {} ", tag)
         }
     };
+    let open_title = match &app.label_filter {
+        Some(label) => format!("{}#{} ", open_title, label),
+        None => open_title,
+    };
+    let open_title = if app.ready_only {
+        format!("{}(ready) ", open_title)
+    } else {
+        open_title
+    };
     let open_list = List::new(open_items).block(
         Block::default()
             .borders(Borders::ALL)
@@ -1233,14 +1463,21 @@ fn ui(frame: &mut Frame, app: &App) {
                 format!(" [{}]", task.notes.len())
             };
             let label = truncate(
-                &format!("[x] {} {}{}", task.id(), task.title, note_hint),
+                &format!("[{}] {} {}{}", task.status.marker(), task.id(), task.title, note_hint),
                 completed_width,
             );
             let style = if i == app.completed_idx && app.focus == Focus::Completed {
                 Style::default().bg(Color::DarkGray).fg(Color::White)
+            } else if task.status == Status::Cancelled {
+                Style::default().fg(Color::DarkGray)
             } else {
                 Style::default().fg(Color::Green)
             };
+            let style = if task.status == Status::Cancelled {
+                style.add_modifier(Modifier::CROSSED_OUT)
+            } else {
+                style
+            };
             ListItem::new(label).style(style)
         })
         .collect();
@@ -1268,6 +1505,7 @@ fn ui(frame: &mut Frame, app: &App) {
     // Detail popup
     if app.show_detail {
         if let Some(task) = app.selected_task() {
+            let link_re = regex::Regex::new(r"([a-z][a-z0-9]*-\d+)").unwrap();
             let area = frame.area();
             let popup_width = (area.width * 90 / 100).max(50).min(area.width.saturating_sub(2));
             let max_popup_height = area.height * 70 / 100;
@@ -1278,8 +1516,14 @@ fn ui(frame: &mut Frame, app: &App) {
 
             frame.render_widget(Clear, popup_area);
 
-            let status_str = if task.done { "done" } else { "open" };
-            let status_color = if task.done { Color::Green } else { Color::Yellow };
+            let status_str = task.status.name();
+            let status_color = match task.status {
+                Status::Done => Color::Green,
+                Status::Cancelled => Color::DarkGray,
+                Status::InProgress => Color::Cyan,
+                Status::Blocked => Color::LightRed,
+                Status::Open => Color::Yellow,
+            };
 
             let mut lines: Vec<Line> = Vec::new();
 
@@ -1300,13 +1544,11 @@ fn ui(frame: &mut Frame, app: &App) {
                         .add_modifier(Modifier::BOLD),
                 ),
             ];
-            if task.priority {
+            if let Some(color) = priority_color(task.priority) {
                 id_spans.push(Span::raw("  "));
                 id_spans.push(Span::styled(
-                    "HIGH PRIORITY",
-                    Style::default()
-                        .fg(Color::Red)
-                        .add_modifier(Modifier::BOLD),
+                    format!("{} PRIORITY", task.priority.name().to_uppercase()),
+                    Style::default().fg(color).add_modifier(Modifier::BOLD),
                 ));
             }
             lines.push(Line::from(id_spans));
@@ -1331,16 +1573,54 @@ fn ui(frame: &mut Frame, app: &App) {
                     Span::raw(&task.date),
                 ]));
             }
+            for (label, date) in [("Due:   ", task.due), ("Sched: ", task.scheduled)] {
+                let Some(date) = date else {
+                    continue;
+                };
+                let style = if label == "Due:   " && task.is_overdue(dates::today()) {
+                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        label,
+                        Style::default()
+                            .fg(Color::Cyan)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(app.date_fmt.format(date), style),
+                ]));
+            }
+            if let Some(at) = task.completed_at {
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        "Done:  ",
+                        Style::default()
+                            .fg(Color::Cyan)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(app.date_fmt.format_timestamp(at)),
+                ]));
+            }
 
-            // Links
-            if !app.detail_links.is_empty() {
+            // Links, then backlinks; the selection index runs across both
+            if !app.detail_links.is_empty() || !app.detail_refs.is_empty() {
                 lines.push(Line::from(""));
-                let links_str = app
-                    .detail_links
+            }
+            let link_groups = [
+                ("Links: ", &app.detail_links, 0),
+                ("Referenced by: ", &app.detail_refs, app.detail_links.len()),
+            ];
+            for (label, ids, first) in link_groups {
+                if ids.is_empty() {
+                    continue;
+                }
+                let links_str = ids
                     .iter()
                     .enumerate()
                     .map(|(i, l)| {
-                        if i == app.detail_link_idx {
+                        if first + i == app.detail_link_idx {
                             format!("[{}]", l)
                         } else {
                             l.clone()
@@ -1350,7 +1630,7 @@ fn ui(frame: &mut Frame, app: &App) {
                     .join("  ");
                 lines.push(Line::from(vec![
                     Span::styled(
-                        "Links: ",
+                        label,
                         Style::default()
                             .fg(Color::Cyan)
                             .add_modifier(Modifier::BOLD),
@@ -1382,23 +1662,32 @@ fn ui(frame: &mut Frame, app: &App) {
                         Style::default().fg(Color::White)
                     };
 
+                    let mut spans = vec![Span::styled(
+                        if is_selected { "▶ - " } else { "  - " },
+                        bullet_style,
+                    )];
+                    // Who wrote the note and when, ahead of the text
+                    let mut meta = Vec::new();
+                    if let Some(at) = note.created_at {
+                        meta.push(app.date_fmt.format_timestamp(at));
+                    }
+                    if let Some(author) = &note.author {
+                        meta.push(format!("by {}", author));
+                    }
+                    if !meta.is_empty() {
+                        spans.push(Span::styled(
+                            format!("{}  ", meta.join(" ")),
+                            Style::default().fg(Color::DarkGray),
+                        ));
+                    }
+
                     // Highlight task links within note text
                     let note_links = parser::extract_links(&note.text);
                     if note_links.is_empty() {
-                        lines.push(Line::from(vec![
-                            Span::styled(
-                                if is_selected { "▶ - " } else { "  - " },
-                                bullet_style,
-                            ),
-                            Span::styled(&note.text, text_style),
-                        ]));
+                        spans.push(Span::styled(&note.text, text_style));
+                        lines.push(Line::from(spans));
                     } else {
-                        let mut spans = vec![Span::styled(
-                            if is_selected { "▶ - " } else { "  - " },
-                            bullet_style,
-                        )];
                         // Simple: render whole text, highlight link portions
-                        let link_re = regex::Regex::new(r"([a-z][a-z0-9]*-\d+)").unwrap();
                         let mut last_end = 0;
                         for mat in link_re.find_iter(&note.text) {
                             if mat.start() > last_end {
@@ -1425,7 +1714,7 @@ fn ui(frame: &mut Frame, app: &App) {
             }
 
             let mut hints = Vec::new();
-            if !app.detail_links.is_empty() {
+            if !app.detail_targets().is_empty() {
                 hints.push("n:next-link f:follow");
             }
             if !app.nav_stack.is_empty() {
@@ -1469,6 +1758,21 @@ fn ui(frame: &mut Frame, app: &App) {
                 .collect();
             format!("Tag: {} | File: {} (j/k, Enter)", app.add_tag, opts.join("  "))
         }
+        Mode::MoveFile => {
+            let opts: Vec<String> = app
+                .pick_file_options
+                .iter()
+                .enumerate()
+                .map(|(i, f)| {
+                    if i == app.pick_file_idx {
+                        format!("[>{}]", f.label)
+                    } else {
+                        f.label.clone()
+                    }
+                })
+                .collect();
+            format!("Move {} to: {} (j/k, Enter)", app.move_id, opts.join("  "))
+        }
         Mode::NoteInput => format!("Note: {}_", app.input),
         Mode::Search => format!("/{}_", app.input),
         Mode::RenameTag => format!("Rename '{}' to: {}_", app.add_tag, app.input),
@@ -1480,6 +1784,7 @@ fn ui(frame: &mut Frame, app: &App) {
         Mode::Normal => "NORMAL",
         Mode::AddTag | Mode::AddTitle => "ADD",
         Mode::PickFile => "FILE",
+        Mode::MoveFile => "MOVE",
         Mode::NoteInput => "NOTE",
         Mode::Search => "SEARCH",
         Mode::RenameTag => "RENAME",
@@ -1493,7 +1798,7 @@ fn ui(frame: &mut Frame, app: &App) {
         .border_style(Style::default().fg(match app.mode {
             Mode::Normal => Color::Gray,
             Mode::ConfirmDeleteNote | Mode::ConfirmDeleteTask => Color::Red,
-            Mode::PickFile => Color::Cyan,
+            Mode::PickFile | Mode::MoveFile => Color::Cyan,
             _ => Color::Green,
         }));
     let status = Paragraph::new(input_text).block(status_block);
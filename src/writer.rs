@@ -1,63 +1,95 @@
 use crate::config::{atomic_write, Config, InsertPosition};
+use crate::dates::{self, DateFormat};
 use crate::error::{Result, TlError};
+use crate::index::TaskIndex;
 use crate::lock::FileLock;
-use crate::parser::{self, find_last_section, find_first_section, find_section_end, today_str};
-use crate::router::{self, RouteResult};
+use crate::parser::{self, find_section_end, Priority, Status};
+use crate::router;
 use crate::state::State;
-use std::path::Path;
-
-/// Ensure today's section exists in the log. Returns the full content after modification.
-/// When `insert_pos` is `Top`, the new section is prepended at line 0.
-/// When `Bottom` (default), it's appended at the end.
-fn ensure_today_section(content: &str, insert_pos: &InsertPosition) -> String {
-    let today = today_str();
-
-    // Check if today's section already exists anywhere in the file
-    let lines: Vec<&str> = content.lines().collect();
-    for line in &lines {
-        if let Some(date) = parser::is_section_header(line) {
-            if date == today {
-                return content.to_string();
-            }
-        }
+use chrono::NaiveDate;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Ensure a section for `date` exists in the log. Returns the content after
+/// modification and the line number of that section's header.
+///
+/// Sections are kept in chronological order: oldest first for `Bottom` files,
+/// newest first for `Top` files. A new header goes right next to the closest
+/// earlier section, so out-of-order or future-dated headers elsewhere in the
+/// file don't drag it out of place. Freeform `### ` headers are ignored when
+/// ordering.
+fn ensure_section(
+    content: &str,
+    date: NaiveDate,
+    insert_pos: &InsertPosition,
+    fmt: &DateFormat,
+) -> (String, usize) {
+    if let Some(line) = parser::find_section_for_date(content, date, fmt) {
+        return (content.to_string(), line);
     }
 
-    match insert_pos {
-        InsertPosition::Bottom => {
-            let mut result = content.to_string();
-            if !result.is_empty() && !result.ends_with('\n') {
-                result.push('\n');
-            }
-            result.push('\n');
-            result.push_str(&format!("### {}\n", today));
-            result
+    let dated: Vec<(usize, NaiveDate)> = parser::section_headers(content, fmt)
+        .into_iter()
+        .filter_map(|(line, d)| d.map(|d| (line, d)))
+        .collect();
+    let mut lines: Vec<&str> = content.lines().collect();
+
+    let insert_at = match insert_pos {
+        // After the last earlier section; if every section is later, before the first one.
+        InsertPosition::Bottom => match dated.iter().rev().find(|(_, d)| *d < date) {
+            Some((line, _)) => find_section_end(content, *line),
+            None => dated.first().map(|(line, _)| *line).unwrap_or(lines.len()),
+        },
+        // Before the first earlier section; if every section is later, after the last one.
+        InsertPosition::Top => match dated.iter().find(|(_, d)| *d < date) {
+            Some((line, _)) => *line,
+            None => dated
+                .last()
+                .map(|(line, _)| find_section_end(content, *line))
+                .unwrap_or(0),
+        },
+    };
+
+    let header = format!("### {}", fmt.format(date));
+    let header_line = if insert_at >= lines.len() {
+        if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+            lines.push("");
         }
-        InsertPosition::Top => {
-            let section = format!("### {}\n\n", today);
-            format!("{}{}", section, content)
+        lines.push(&header);
+        lines.len() - 1
+    } else {
+        let mut at = insert_at;
+        if at > 0 && !lines[at - 1].trim().is_empty() {
+            lines.insert(at, "");
+            at += 1;
         }
-    }
+        lines.insert(at, "");
+        lines.insert(at, &header);
+        at
+    };
+
+    let mut result = lines.join("\n");
+    result.push('\n');
+    (result, header_line)
 }
 
 /// Ensure a single log file exists and has today's section.
-fn init_log_file(path: &Path, insert_pos: &InsertPosition) -> Result<()> {
+fn init_log_file(path: &Path, insert_pos: &InsertPosition, fmt: &DateFormat) -> Result<()> {
     if !path.exists() {
         if let Some(parent) = path.parent() {
             if !parent.exists() {
                 std::fs::create_dir_all(parent)?;
             }
         }
-        let today = today_str();
-        let content = format!("### {}\n", today);
+        let content = format!("### {}\n", fmt.today_str());
         atomic_write(path, content.as_bytes())?;
     } else {
         let content = std::fs::read_to_string(path)?;
         if content.trim().is_empty() {
-            let today = today_str();
-            let content = format!("### {}\n", today);
+            let content = format!("### {}\n", fmt.today_str());
             atomic_write(path, content.as_bytes())?;
         } else {
-            let updated = ensure_today_section(&content, insert_pos);
+            let (updated, _) = ensure_section(&content, dates::today(), insert_pos, fmt);
             if updated != content {
                 atomic_write(path, updated.as_bytes())?;
             }
@@ -91,11 +123,12 @@ pub fn init(log_path: Option<&str>) -> Result<()> {
     }
 
     let config = Config::load()?;
+    let fmt = config.date_fmt()?;
 
     // Initialize all registered files
     let files = config.effective_files();
     for entry in &files {
-        init_log_file(&entry.resolved_path(), &entry.insert)?;
+        init_log_file(&entry.resolved_path(), &entry.insert, &fmt)?;
     }
 
     Ok(())
@@ -104,19 +137,16 @@ pub fn init(log_path: Option<&str>) -> Result<()> {
 /// Add a new task with the given tag and title.
 /// Returns the assigned task ID string.
 pub fn add_task(tag: &str, title: &str) -> Result<String> {
-    add_task_with_priority(tag, title, false)
+    add_task_with_priority(tag, title, Priority::None)
 }
 
 /// Add a new task with the given tag, title, and priority.
 /// Routes to the correct file automatically. For ambiguous routes (multiple
 /// variable files), defaults to the first variable file. Use
 /// `add_task_to_file` for explicit file targeting (TUI picker).
-pub fn add_task_with_priority(tag: &str, title: &str, priority: bool) -> Result<String> {
+pub fn add_task_with_priority(tag: &str, title: &str, priority: Priority) -> Result<String> {
     let config = Config::load()?;
-    let log_path = match router::resolve_file_for_tag(&config, tag)? {
-        RouteResult::Resolved(p) => p,
-        RouteResult::Ambiguous(files) => files[0].resolved_path(),
-    };
+    let log_path = router::file_for_new_task(&config, tag, None)?;
     add_task_to_file(tag, title, priority, &log_path)
 }
 
@@ -130,123 +160,365 @@ fn insert_position_for_path(config: &Config, path: &Path) -> InsertPosition {
     InsertPosition::default()
 }
 
+/// Indentation for new note lines: whatever the file already uses, so
+/// hand-maintained files never end up with mixed tabs and spaces, otherwise
+/// `note_indent` spaces.
+fn note_indent(config: &Config, content: &str) -> String {
+    parser::detect_note_indent(content).unwrap_or_else(|| " ".repeat(config.note_indent))
+}
+
+/// Locate a task by ID through the index and parse its file with a scan
+/// window wide enough to include the task's section, however far back it is.
+fn load_task_file(
+    config: &Config,
+    fmt: &DateFormat,
+    id: &str,
+) -> Result<(PathBuf, String, Vec<parser::Section>)> {
+    let (log_path, line) = router::find_file_for_task(config, id)?;
+    let content = std::fs::read_to_string(&log_path)?;
+    let window = parser::window_covering(&content, line, config.scan_window_lines);
+    let sections = parser::parse_log(&content, window, fmt);
+    Ok((log_path, content, sections))
+}
+
 /// Add a new task to a specific file. Called by the TUI after the user picks
 /// a file from the picker, or by the CLI auto-route.
-pub fn add_task_to_file(tag: &str, title: &str, priority: bool, log_path: &Path) -> Result<String> {
-    if !tag.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()) || tag.is_empty() {
-        return Err(TlError::Parse(
-            "tag must be lowercase alphanumeric".to_string(),
-        ));
+pub fn add_task_to_file(
+    tag: &str,
+    title: &str,
+    priority: Priority,
+    log_path: &Path,
+) -> Result<String> {
+    validate_tag(tag)?;
+
+    let _lock = FileLock::acquire()?;
+    let config = Config::load()?;
+    insert_new_task(&config, tag, title, priority, log_path, &[], None)
+}
+
+/// Add a new task together with its first notes, written in one go under the
+/// lock so the task never appears without them. `file` is an optional file
+/// label, routed like `router::file_for_new_task` while the lock is held.
+/// Title and notes must each fit on one line.
+pub fn add_task_with_notes(
+    tag: &str,
+    title: &str,
+    priority: Priority,
+    file: Option<&str>,
+    notes: &[String],
+    author: Option<&str>,
+) -> Result<String> {
+    validate_tag(tag)?;
+    validate_single_line("title", title)?;
+    for note in notes {
+        validate_single_line("note", note)?;
     }
 
     let _lock = FileLock::acquire()?;
     let config = Config::load()?;
+    let log_path = router::file_for_new_task(&config, tag, file)?;
+    insert_new_task(&config, tag, title, priority, &log_path, notes, author)
+}
+
+/// Write a new task and its notes at the end of today's section of
+/// `log_path`. The caller holds the lock.
+fn insert_new_task(
+    config: &Config,
+    tag: &str,
+    title: &str,
+    priority: Priority,
+    log_path: &Path,
+    notes: &[String],
+    author: Option<&str>,
+) -> Result<String> {
+    let fmt = config.date_fmt()?;
     let mut state = State::load()?;
-    let insert_pos = insert_position_for_path(&config, log_path);
+    let insert_pos = insert_position_for_path(config, log_path);
 
     let content = std::fs::read_to_string(log_path)?;
-    let content = ensure_today_section(&content, &insert_pos);
+    let (content, section_line) = ensure_section(&content, dates::today(), &insert_pos, &fmt);
 
-    // Scan ALL files for the max ID of this tag (IDs are globally unique)
-    let mut max_in_all: u64 = 0;
-    for path in config.all_file_paths() {
-        if !path.exists() {
-            continue;
-        }
-        let c = std::fs::read_to_string(&path)?;
-        let secs = parser::parse_log(&c, config.scan_window_lines);
-        let max = secs
-            .iter()
-            .flat_map(|s| &s.tasks)
-            .filter(|t| t.tag == tag)
-            .map(|t| t.number)
-            .max()
-            .unwrap_or(0);
-        if max > max_in_all {
-            max_in_all = max;
-        }
+    let number = allocate_number(config, &mut state, tag)?;
+    let id = format!("{}-{}", tag, number);
+
+    let insert_at = parser::find_section_insert_point(&content, section_line);
+
+    let task_line = parser::Task {
+        tag: tag.to_string(),
+        number,
+        title: title.to_string(),
+        priority,
+        ..Default::default()
     }
+    .render(&fmt);
+
+    let indent = note_indent(config, &content);
+    let now = dates::now();
+    let note_lines = notes.iter().map(|text| {
+        parser::Note {
+            created_at: Some(now),
+            author: author.map(|a| a.to_string()),
+            text: text.to_string(),
+            ..Default::default()
+        }
+        .render(&indent, &fmt)
+    });
 
-    // Ensure state counter is at least as high as what's across all files
-    state.sync_min(tag, max_in_all);
-    let number = state.next_id(tag);
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+    lines.splice(insert_at..insert_at, std::iter::once(task_line).chain(note_lines));
+
+    write_lines(log_path, lines)?;
+    state.save()?;
+
+    Ok(id)
+}
+
+/// Add a new task as a subtask of `parent_id`, in the parent's file, after
+/// the parent's notes and existing subtasks.
+pub fn add_subtask(
+    parent_id: &str,
+    tag: &str,
+    title: &str,
+    priority: Priority,
+) -> Result<String> {
+    validate_tag(tag)?;
+
+    let _lock = FileLock::acquire()?;
+    let config = Config::load()?;
+    let fmt = config.date_fmt()?;
+    let mut state = State::load()?;
+
+    let (log_path, content, sections) = load_task_file(&config, &fmt, parent_id)?;
+    router::check_tag_for_file(&config, tag, &log_path)?;
+    let parent = parser::find_task(&sections, parent_id)?;
+
+    // Insert after the last line of the parent's block (notes and subtasks)
+    let last_line = std::iter::once(parent)
+        .chain(parser::descendants(&sections, parent))
+        .flat_map(|t| std::iter::once(t.line_number).chain(t.notes.iter().map(|n| n.line_number)))
+        .max()
+        .unwrap_or(parent.line_number);
+
+    let number = allocate_number(&config, &mut state, tag)?;
     let id = format!("{}-{}", tag, number);
 
-    // For "top" files, use the first section; for "bottom", use the last.
-    let (section_line, _) = match insert_pos {
-        InsertPosition::Top => find_first_section(&content),
-        InsertPosition::Bottom => find_last_section(&content),
+    let task_line = parser::Task {
+        indent: format!("{}{}", parent.indent, note_indent(&config, &content)),
+        tag: tag.to_string(),
+        number,
+        title: title.to_string(),
+        priority,
+        ..Default::default()
     }
-    .ok_or_else(|| TlError::Other("no section found in log".to_string()))?;
-    let section_end = find_section_end(&content, section_line);
+    .render(&fmt);
 
-    let priority_marker = if priority { "!" } else { "" };
-    let task_line = format!("- [ ] {}{} {}", id, priority_marker, title);
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+    lines.insert(last_line + 1, task_line);
 
-    let mut lines: Vec<&str> = content.lines().collect();
+    write_lines(&log_path, lines)?;
+    state.save()?;
 
-    if section_end >= lines.len() {
-        lines.push(&task_line);
-    } else {
-        lines.insert(section_end, &task_line);
+    Ok(id)
+}
+
+fn validate_tag(tag: &str) -> Result<()> {
+    if !tag.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()) || tag.is_empty() {
+        return Err(TlError::Parse(
+            "tag must be lowercase alphanumeric".to_string(),
+        ));
     }
+    Ok(())
+}
 
-    let mut new_content = lines.join("\n");
-    if !new_content.ends_with('\n') {
-        new_content.push('\n');
+/// Titles and notes are single lines in the log; an embedded newline would
+/// write lines of its own, possibly ones that parse as tasks.
+fn validate_single_line(what: &str, text: &str) -> Result<()> {
+    if text.contains(['\n', '\r']) {
+        return Err(TlError::Parse(format!("{} must be a single line", what)));
     }
+    Ok(())
+}
 
-    atomic_write(log_path, new_content.as_bytes())?;
-    state.save()?;
+/// Next free number for `tag`. The state counter is synced against the max ID
+/// of this tag across the full history of ALL files (IDs are globally unique).
+fn allocate_number(config: &Config, state: &mut State, tag: &str) -> Result<u64> {
+    let max_in_all = TaskIndex::open(config)?.max_number(tag);
+    state.sync_min(tag, max_in_all);
+    Ok(state.next_id(tag))
+}
 
-    Ok(id)
+/// What happened when a task was completed, beyond marking it done.
+#[derive(Debug, Default)]
+pub struct Completion {
+    /// For recurring tasks: the ID of the fresh open copy and the date of the
+    /// section it was added to.
+    pub recurred: Option<(String, NaiveDate)>,
+    /// IDs of blockers that were still open. The task is completed anyway;
+    /// callers should warn.
+    pub open_blockers: Vec<String>,
 }
 
-/// Mark a task as done by its ID.
-pub fn complete_task(id: &str) -> Result<()> {
+/// Mark a task as done by its ID. A recurring task (`every:<rule>`) gets a
+/// fresh open copy with a new ID in the section of its next occurrence; its
+/// due date, if any, moves along. Open blockers don't stop completion but are
+/// reported back.
+pub fn complete_task(id: &str) -> Result<Completion> {
     let _lock = FileLock::acquire()?;
     let config = Config::load()?;
+    let fmt = config.date_fmt()?;
 
-    let log_path = router::find_file_for_task(&config, id)?;
-    let content = std::fs::read_to_string(&log_path)?;
-
-    let sections = parser::parse_log(&content, config.scan_window_lines);
+    let (log_path, content, sections) = load_task_file(&config, &fmt, id)?;
     let task = parser::find_task(&sections, id)?;
 
-    if task.done {
+    if task.status == Status::Done {
         return Err(TlError::Other(format!("task {} is already done", id)));
     }
+    if task.status.is_closed() {
+        return Err(TlError::Other(format!(
+            "task {} is {}; reopen it with undo first",
+            id,
+            task.status.name()
+        )));
+    }
 
-    let stamp = chrono::Local::now().format("%d/%m/%Y %I:%M%p").to_string();
+    let done = parser::Task {
+        status: Status::Done,
+        completed_at: Some(dates::now()),
+        ..task.clone()
+    };
 
     let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
-    let line = &mut lines[task.line_number];
-    *line = format!("{} ({})", line.replacen("[ ]", "[x]", 1), stamp);
+    lines[task.line_number] = done.render(&fmt);
 
-    let mut new_content = lines.join("\n");
-    if !new_content.ends_with('\n') {
-        new_content.push('\n');
+    let mut completion = Completion {
+        open_blockers: open_blockers_of(&config, &fmt, task)?,
+        ..Default::default()
+    };
+    if let Some(rule) = task.recurrence {
+        // Next occurrence after the due date (else the scheduled date, else
+        // today), skipping any that were missed while the task sat overdue
+        let today = dates::today();
+        let anchor = task.due.or(task.scheduled).unwrap_or(today);
+        let mut next = rule.next_after(anchor);
+        while next < today {
+            next = rule.next_after(next);
+        }
+        // Both date markers move by the same amount, keeping their spacing
+        let shift = next - anchor;
+        let title = parser::with_due(&task.title, task.due.map(|d| d + shift));
+        let title = parser::with_scheduled(&title, task.scheduled.map(|d| d + shift));
+
+        let mut state = State::load()?;
+        let number = allocate_number(&config, &mut state, &task.tag)?;
+        // The copy always goes top-level into the next occurrence's section,
+        // so a recurring subtask's copy isn't nested under its parent
+        let copy = parser::Task {
+            tag: task.tag.clone(),
+            number,
+            title,
+            priority: task.priority,
+            priority_marker: task.priority_marker.clone(),
+            ..Default::default()
+        };
+
+        let insert_pos = insert_position_for_path(&config, &log_path);
+        let (modified, section_line) = ensure_section(&lines.join("\n"), next, &insert_pos, &fmt);
+        let insert_at = parser::find_section_insert_point(&modified, section_line);
+        lines = modified.lines().map(|l| l.to_string()).collect();
+        lines.insert(insert_at, copy.render(&fmt));
+
+        write_lines(&log_path, lines)?;
+        state.save()?;
+        completion.recurred = Some((copy.id(), next));
+        return Ok(completion);
     }
 
-    atomic_write(&log_path, new_content.as_bytes())?;
-    Ok(())
+    write_lines(&log_path, lines)?;
+    Ok(completion)
 }
 
-/// Undo a completed task: move it (with notes) to today's section as open.
+/// Move a task to another state in place: in progress, blocked, back to open,
+/// or cancelled (which stamps it like a completion). Done tasks go through
+/// `complete_task`, and closed tasks are reopened with `undo_task`.
+pub fn set_status(id: &str, status: Status) -> Result<()> {
+    if status == Status::Done {
+        return complete_task(id).map(|_| ());
+    }
+
+    let _lock = FileLock::acquire()?;
+    let config = Config::load()?;
+    let fmt = config.date_fmt()?;
+
+    let (log_path, content, sections) = load_task_file(&config, &fmt, id)?;
+    let task = parser::find_task(&sections, id)?;
+
+    if task.status == status {
+        return Err(TlError::Other(format!("task {} is already {}", id, status.name())));
+    }
+    if task.status.is_closed() {
+        return Err(TlError::Other(format!(
+            "task {} is {}; reopen it with undo first",
+            id,
+            task.status.name()
+        )));
+    }
+
+    let updated = parser::Task {
+        status,
+        completed_at: (status == Status::Cancelled).then(dates::now),
+        ..task.clone()
+    };
+
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+    lines[task.line_number] = updated.render(&fmt);
+    write_lines(&log_path, lines)
+}
+
+/// Undo a completed or cancelled task: move it (with notes and subtasks) to
+/// today's section as open. Subtasks are reopened in place so they stay under
+/// their parent.
 pub fn undo_task(id: &str) -> Result<()> {
     let _lock = FileLock::acquire()?;
     let config = Config::load()?;
 
-    let log_path = router::find_file_for_task(&config, id)?;
-    let insert_pos = insert_position_for_path(&config, &log_path);
-    let content = std::fs::read_to_string(&log_path)?;
-    let content = ensure_today_section(&content, &insert_pos);
+    let fmt = config.date_fmt()?;
 
-    let sections = parser::parse_log(&content, config.scan_window_lines);
+    let (log_path, content, sections) = load_task_file(&config, &fmt, id)?;
+    let insert_pos = insert_position_for_path(&config, &log_path);
     let task = parser::find_task(&sections, id)?;
 
-    if !task.done {
-        return Err(TlError::Other(format!("task {} is not done", id)));
+    if !task.status.is_closed() {
+        return Err(TlError::Other(format!("task {} is not done or cancelled", id)));
+    }
+
+    let completed_on = task
+        .completed_at
+        .map(|at| fmt.format(at.date()))
+        .unwrap_or_else(|| task.date.clone());
+    let verb = match task.status {
+        Status::Cancelled => "cancelled",
+        _ => "completed",
+    };
+    let reopened_note = parser::Note {
+        created_at: Some(dates::now()),
+        text: format!("reopened (was {} on {})", verb, completed_on),
+        ..Default::default()
+    };
+    let indent = note_indent(&config, &content);
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+
+    if task.parent.is_some() {
+        let reopened = parser::Task {
+            status: Status::Open,
+            completed_at: None,
+            ..task.clone()
+        };
+        lines[task.line_number] = reopened.render(&fmt);
+        lines.insert(
+            task.line_number + 1,
+            reopened_note.render(&format!("{}{}", task.indent, indent), &fmt),
+        );
+        return write_lines(&log_path, lines);
     }
 
     // Collect line numbers to remove (task line + all note lines)
@@ -255,78 +527,218 @@ pub fn undo_task(id: &str) -> Result<()> {
     for note in &task.notes {
         lines_to_remove.push(note.line_number);
     }
-    lines_to_remove.sort();
 
-    // Build the reopened task line (strip completion timestamp, flip to [ ])
-    let priority_marker = if task.priority { "!" } else { "" };
-    let task_line = format!("- [ ] {}{} {}", task.id(), priority_marker, task.title);
+    // Build the reopened task line (drop completion timestamp, flip to [ ])
+    let reopened = parser::Task {
+        indent: String::new(),
+        status: Status::Open,
+        completed_at: None,
+        ..task.clone()
+    };
+    let mut to_insert = vec![reopened.render(&fmt)];
 
-    // Build note lines to carry over, plus a reopened note
-    let stamp = chrono::Local::now().format("%d/%m/%Y %I:%M%p").to_string();
-    let mut new_note_lines: Vec<String> = Vec::new();
-    new_note_lines.push(format!("\t- [{}] reopened (was completed on {})", stamp, task.date));
+    // Note lines to carry over, plus a reopened note
+    to_insert.push(reopened_note.render(&indent, &fmt));
     for note in &task.notes {
-        new_note_lines.push(format!("\t- {}", note.text));
+        to_insert.push(note.render(&indent, &fmt));
+    }
+
+    // Subtasks move along verbatim, re-indented relative to the parent
+    for sub in parser::descendants(&sections, task) {
+        for ln in std::iter::once(sub.line_number).chain(sub.notes.iter().map(|n| n.line_number)) {
+            lines_to_remove.push(ln);
+            let line = &lines[ln];
+            to_insert.push(line.strip_prefix(task.indent.as_str()).unwrap_or(line).to_string());
+        }
     }
+    lines_to_remove.sort();
 
     // Remove old lines (reverse order to keep indices valid)
-    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
     for &ln in lines_to_remove.iter().rev() {
         if ln < lines.len() {
             lines.remove(ln);
         }
     }
 
-    // Re-join to find today's section in the modified content
+    // Re-join to find (or create) today's section in the modified content
     let modified = lines.join("\n");
-    let find_section = match insert_pos {
-        InsertPosition::Top => find_first_section(&modified),
-        InsertPosition::Bottom => find_last_section(&modified),
-    };
-    let (section_line, _) = find_section
-        .ok_or_else(|| TlError::Other("no section found in log".to_string()))?;
-    let section_end = find_section_end(&modified, section_line);
+    let (modified, section_line) = ensure_section(&modified, dates::today(), &insert_pos, &fmt);
+    let insert_at = parser::find_section_insert_point(&modified, section_line);
 
-    // Re-split for insertion
+    // Re-split and insert at end of today's section
     let mut lines: Vec<String> = modified.lines().map(|l| l.to_string()).collect();
+    lines.splice(insert_at..insert_at, to_insert);
 
-    // Insert task + notes at end of today's section
-    let insert_at = if section_end >= lines.len() {
-        lines.len()
+    write_lines(&log_path, lines)
+}
+
+/// Move a task with its notes and subtasks to the section for `date` in the
+/// file labelled `file`. The file defaults to the one the task is in now, the
+/// date to its current section's date (today under a freeform header). Fixed
+/// file rules apply to the task and each subtask. A dated section left with
+/// nothing in it is removed. Returns the destination file label and date.
+pub fn move_task(
+    id: &str,
+    file: Option<&str>,
+    date: Option<NaiveDate>,
+) -> Result<(String, NaiveDate)> {
+    let _lock = FileLock::acquire()?;
+    let config = Config::load()?;
+    let fmt = config.date_fmt()?;
+
+    let (src_path, content, sections) = load_task_file(&config, &fmt, id)?;
+    let task = parser::find_task(&sections, id)?;
+
+    let files = config.effective_files();
+    let dest = match file {
+        Some(label) => files.iter().find(|f| f.label == label),
+        None => files.iter().find(|f| f.resolved_path() == src_path),
+    }
+    .ok_or_else(|| {
+        TlError::Config(format!("no log file labelled '{}'", file.unwrap_or_default()))
+    })?;
+    let dest_path = dest.resolved_path();
+    let date = date.or(task.section_date).unwrap_or_else(dates::today);
+    if dest_path == src_path && task.section_date == Some(date) {
+        return Err(TlError::Other(format!(
+            "{} is already in {} under {}",
+            id,
+            dest.label,
+            fmt.format(date)
+        )));
+    }
+
+    let subtasks = parser::descendants(&sections, task);
+    for t in std::iter::once(task).chain(subtasks.iter().copied()) {
+        router::check_tag_for_file(&config, &t.tag, &dest_path)?;
+    }
+
+    let (remaining, mut block) = lift_block(&content, &sections, task, &fmt);
+
+    let dest_content = if dest_path == src_path {
+        remaining.join("\n")
     } else {
-        section_end
+        // Match the destination file's note indentation
+        let dest_content = std::fs::read_to_string(&dest_path)?;
+        let (from, to) = (note_indent(&config, &content), note_indent(&config, &dest_content));
+        for line in &mut block {
+            *line = reindent(line, &from, &to);
+        }
+        dest_content
     };
 
-    // Insert in reverse so indices stay correct
-    let mut to_insert = vec![task_line];
-    to_insert.extend(new_note_lines);
+    let insert_pos = insert_position_for_path(&config, &dest_path);
+    let dest_lines = place_block(&dest_content, block, date, &insert_pos, &fmt);
 
-    for (i, line) in to_insert.into_iter().enumerate() {
-        if insert_at + i >= lines.len() {
-            lines.push(line);
-        } else {
-            lines.insert(insert_at + i, line);
+    // Write the destination first so a failure never loses the task
+    write_lines(&dest_path, dest_lines)?;
+    if dest_path != src_path {
+        write_lines(&src_path, remaining)?;
+    }
+    Ok((dest.label.clone(), date))
+}
+
+/// Take `task` with its notes and subtasks out of `content`, outdented so the
+/// task is top-level wherever it lands. Returns the remaining lines and the
+/// lifted block. A date section left with nothing in it goes too; freeform
+/// headers are structure the user wrote, so they stay even when empty.
+fn lift_block(
+    content: &str,
+    sections: &[parser::Section],
+    task: &parser::Task,
+    fmt: &DateFormat,
+) -> (Vec<String>, Vec<String>) {
+    let mut block_lines: Vec<usize> = std::iter::once(task)
+        .chain(parser::descendants(sections, task))
+        .flat_map(|t| std::iter::once(t.line_number).chain(t.notes.iter().map(|n| n.line_number)))
+        .collect();
+    block_lines.sort();
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+    let block: Vec<String> = block_lines
+        .iter()
+        .map(|&ln| {
+            let line = &lines[ln];
+            line.strip_prefix(task.indent.as_str()).unwrap_or(line).to_string()
+        })
+        .collect();
+    for &ln in block_lines.iter().rev() {
+        lines.remove(ln);
+    }
+    let source_section = parser::section_headers(content, fmt)
+        .into_iter()
+        .rev()
+        .find(|(line, _)| *line < task.line_number);
+    if let Some((header, Some(_))) = source_section {
+        drop_empty_section(&mut lines, header);
+    }
+    (lines, block)
+}
+
+/// Append `block` to the section for `date` in `content`, creating the
+/// section if there isn't one. Returns the new lines.
+fn place_block(
+    content: &str,
+    block: Vec<String>,
+    date: NaiveDate,
+    insert_pos: &InsertPosition,
+    fmt: &DateFormat,
+) -> Vec<String> {
+    let (content, section_line) = ensure_section(content, date, insert_pos, fmt);
+    let insert_at = parser::find_section_insert_point(&content, section_line);
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+    lines.splice(insert_at..insert_at, block);
+    lines
+}
+
+/// Swap each leading `from` indentation unit on `line` for `to`.
+fn reindent(line: &str, from: &str, to: &str) -> String {
+    let mut rest = line;
+    let mut depth = 0;
+    while let Some(r) = rest.strip_prefix(from).filter(|_| !from.is_empty()) {
+        rest = r;
+        depth += 1;
+    }
+    format!("{}{}", to.repeat(depth), rest)
+}
+
+/// Remove the section headed at `header` if only blank lines are left in it.
+/// A section at the end of the file takes the blank lines before it along.
+fn drop_empty_section(lines: &mut Vec<String>, header: usize) {
+    let end = lines
+        .iter()
+        .skip(header + 1)
+        .position(|l| parser::is_section_header(l).is_some())
+        .map_or(lines.len(), |i| header + 1 + i);
+    if lines[header + 1..end].iter().any(|l| !l.trim().is_empty()) {
+        return;
+    }
+    let mut start = header;
+    if end == lines.len() {
+        while start > 0 && lines[start - 1].trim().is_empty() {
+            start -= 1;
         }
     }
+    lines.drain(start..end);
+}
 
+/// Join lines back into file content (with a trailing newline) and write it.
+fn write_lines(path: &Path, lines: Vec<String>) -> Result<()> {
     let mut new_content = lines.join("\n");
     if !new_content.ends_with('\n') {
         new_content.push('\n');
     }
-
-    atomic_write(&log_path, new_content.as_bytes())?;
-    Ok(())
+    atomic_write(path, new_content.as_bytes())
 }
 
-/// Add a note under a task by its ID.
-pub fn add_note(id: &str, text: &str) -> Result<()> {
+/// Add a note under a task by its ID. `author` is recorded as a
+/// "(by <author>)" marker after the timestamp, e.g. "cli" or "agent".
+pub fn add_note(id: &str, text: &str, author: Option<&str>) -> Result<()> {
+    validate_single_line("note", text)?;
     let _lock = FileLock::acquire()?;
     let config = Config::load()?;
+    let fmt = config.date_fmt()?;
 
-    let log_path = router::find_file_for_task(&config, id)?;
-    let content = std::fs::read_to_string(&log_path)?;
-
-    let sections = parser::parse_log(&content, config.scan_window_lines);
+    let (log_path, content, sections) = load_task_file(&config, &fmt, id)?;
     let task = parser::find_task(&sections, id)?;
 
     let insert_after = if task.notes.is_empty() {
@@ -335,9 +747,14 @@ pub fn add_note(id: &str, text: &str) -> Result<()> {
         task.notes.last().unwrap().line_number
     };
 
-    let indent = "\t";
-    let stamp = chrono::Local::now().format("%d/%m/%Y %I:%M%p").to_string();
-    let note_line = format!("{}- [{}] {}", indent, stamp, text);
+    let indent = format!("{}{}", task.indent, note_indent(&config, &content));
+    let note_line = parser::Note {
+        created_at: Some(dates::now()),
+        author: author.map(|a| a.to_string()),
+        text: text.to_string(),
+        ..Default::default()
+    }
+    .render(&indent, &fmt);
 
     let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
     lines.insert(insert_after + 1, note_line);
@@ -355,11 +772,9 @@ pub fn add_note(id: &str, text: &str) -> Result<()> {
 pub fn delete_note(id: &str, note_index: usize) -> Result<()> {
     let _lock = FileLock::acquire()?;
     let config = Config::load()?;
+    let fmt = config.date_fmt()?;
 
-    let log_path = router::find_file_for_task(&config, id)?;
-    let content = std::fs::read_to_string(&log_path)?;
-
-    let sections = parser::parse_log(&content, config.scan_window_lines);
+    let (log_path, content, sections) = load_task_file(&config, &fmt, id)?;
     let task = parser::find_task(&sections, id)?;
 
     if note_index >= task.notes.len() {
@@ -394,38 +809,18 @@ pub fn edit_task(id: &str, new_title: &str) -> Result<()> {
 
     let _lock = FileLock::acquire()?;
     let config = Config::load()?;
+    let fmt = config.date_fmt()?;
 
-    let log_path = router::find_file_for_task(&config, id)?;
-    let content = std::fs::read_to_string(&log_path)?;
-
-    let sections = parser::parse_log(&content, config.scan_window_lines);
+    let (log_path, content, sections) = load_task_file(&config, &fmt, id)?;
     let task = parser::find_task(&sections, id)?;
 
-    let status = if task.done { "x" } else { " " };
-    let priority_marker = if task.priority { "!" } else { "" };
-
-    // For done tasks, preserve the completion timestamp at the end
-    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
-    let old_line = &lines[task.line_number];
-
-    // Check if there's a trailing " (timestamp)" on done tasks
-    let trailing = if task.done {
-        // The timestamp is appended after the title as " (DD/MM/YYYY HH:MMAM/PM)"
-        if let Some(paren_pos) = old_line.rfind(" (") {
-            &old_line[paren_pos..]
-        } else {
-            ""
-        }
-    } else {
-        ""
+    let edited = parser::Task {
+        title: new_title.to_string(),
+        ..task.clone()
     };
 
-    let new_line = format!(
-        "{}- [{}] {}{} {}{}",
-        task.indent, status, task.id(), priority_marker, new_title, trailing
-    );
-
-    lines[task.line_number] = new_line;
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+    lines[task.line_number] = edited.render(&fmt);
 
     let mut new_content = lines.join("\n");
     if !new_content.ends_with('\n') {
@@ -436,22 +831,23 @@ pub fn edit_task(id: &str, new_title: &str) -> Result<()> {
     Ok(())
 }
 
-/// Delete a task and all its notes by its ID.
+/// Delete a task, its notes and its subtasks by its ID.
 pub fn delete_task(id: &str) -> Result<()> {
     let _lock = FileLock::acquire()?;
     let config = Config::load()?;
+    let fmt = config.date_fmt()?;
 
-    let log_path = router::find_file_for_task(&config, id)?;
-    let content = std::fs::read_to_string(&log_path)?;
-
-    let sections = parser::parse_log(&content, config.scan_window_lines);
+    let (log_path, content, sections) = load_task_file(&config, &fmt, id)?;
     let task = parser::find_task(&sections, id)?;
 
-    // Collect all line numbers to remove: task line + all note lines
+    // Collect all line numbers to remove: task line + all note lines, for the
+    // task and every subtask under it
     let mut lines_to_remove: Vec<usize> = Vec::new();
-    lines_to_remove.push(task.line_number);
-    for note in &task.notes {
-        lines_to_remove.push(note.line_number);
+    for t in std::iter::once(task).chain(parser::descendants(&sections, task)) {
+        lines_to_remove.push(t.line_number);
+        for note in &t.notes {
+            lines_to_remove.push(note.line_number);
+        }
     }
     lines_to_remove.sort();
 
@@ -472,125 +868,268 @@ pub fn delete_task(id: &str) -> Result<()> {
     Ok(())
 }
 
-/// Rename a tag across ALL log files and update state.
-pub fn rename_tag(old_tag: &str, new_tag: &str) -> Result<()> {
-    if !new_tag
-        .chars()
-        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
-        || new_tag.is_empty()
-    {
-        return Err(TlError::Parse(
-            "tag must be lowercase alphanumeric".to_string(),
-        ));
-    }
-
-    let _lock = FileLock::acquire()?;
-    let config = Config::load()?;
-    let mut state = State::load()?;
+/// One line rewritten by a bulk edit such as a tag rename.
+#[derive(Debug, Clone)]
+pub struct LineChange {
+    /// Label of the file the line is in.
+    pub file: String,
+    /// 0-based line number.
+    pub line: usize,
+    pub before: String,
+    pub after: String,
+}
 
-    let task_re = regex::Regex::new(&format!(
-        r"^(\s*- \[[ x]\] ){}-(\d+)",
-        regex::escape(old_tag)
-    ))
-    .map_err(|e| TlError::Other(e.to_string()))?;
+/// A file and its rewritten lines, ready for `write_lines`.
+type FileLines = (PathBuf, Vec<String>);
 
-    let mut found_any = false;
+/// Apply `map` to every task ID reference on task and note lines in every
+/// file (freeform text is left alone). Returns the changed lines and, for
+/// each file with changes, its rewritten lines; nothing is written.
+fn rewrite_links_in_files(
+    config: &Config,
+    fmt: &DateFormat,
+    map: impl Fn(&str) -> Option<String> + Copy,
+) -> Result<(Vec<LineChange>, Vec<FileLines>)> {
+    let mut changes = Vec::new();
+    let mut rewrites = Vec::new();
 
-    for log_path in config.all_file_paths() {
+    for entry in config.effective_files() {
+        let log_path = entry.resolved_path();
         if !log_path.exists() {
             continue;
         }
         let content = std::fs::read_to_string(&log_path)?;
-        let sections = parser::parse_log(&content, config.scan_window_lines);
-        let has_old = sections
-            .iter()
-            .flat_map(|s| &s.tasks)
-            .any(|t| t.tag == old_tag);
-
-        if !has_old {
+        let (lines, changed) = rewrite_links_in_content(&content, fmt, map);
+        if changed.is_empty() {
             continue;
         }
-        found_any = true;
-
-        let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
-        for line in &mut lines {
-            if let Some(caps) = task_re.captures(&line.clone()) {
-                let prefix = &caps[1];
-                let number = &caps[2];
-                let rest = &line[caps[0].len()..];
-                *line = format!("{}{}-{}{}", prefix, new_tag, number, rest);
-            }
-        }
+        changes.extend(changed.into_iter().map(|(line, before, after)| LineChange {
+            file: entry.label.clone(),
+            line,
+            before,
+            after,
+        }));
+        rewrites.push((log_path, lines));
+    }
 
-        let mut new_content = lines.join("\n");
-        if !new_content.ends_with('\n') {
-            new_content.push('\n');
+    Ok((changes, rewrites))
+}
+
+/// `rewrite_links_in_files` for one file's content. Alias notes ("was
+/// dev-4") record an ID as it was, so they're left alone. Returns the lines
+/// and the (line, before, after) of each change.
+fn rewrite_links_in_content(
+    content: &str,
+    fmt: &DateFormat,
+    map: impl Fn(&str) -> Option<String> + Copy,
+) -> (Vec<String>, Vec<(usize, String, String)>) {
+    let sections = parser::parse_log(content, usize::MAX, fmt);
+    let task_lines: std::collections::BTreeSet<usize> = sections
+        .iter()
+        .flat_map(|s| &s.tasks)
+        .flat_map(|t| {
+            let notes = t.notes.iter().filter(|n| parser::alias_of(&n.text).is_none());
+            std::iter::once(t.line_number).chain(notes.map(|n| n.line_number))
+        })
+        .collect();
+
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+    let mut changes = Vec::new();
+    for idx in task_lines {
+        let Some(line) = lines.get_mut(idx) else {
+            continue;
+        };
+        let rewritten = parser::rewrite_links(line, map);
+        if rewritten != *line {
+            changes.push((idx, std::mem::replace(line, rewritten.clone()), rewritten));
         }
+    }
+    (lines, changes)
+}
+
+/// Rename a tag across ALL log files and update state. Task lines are
+/// renamed, and references like `old-3` in any task title or note are
+/// rewritten to `new-3` so links keep working. With `dry_run` nothing is
+/// written; either way the changed lines are returned.
+pub fn rename_tag(old_tag: &str, new_tag: &str, dry_run: bool) -> Result<Vec<LineChange>> {
+    validate_tag(new_tag)?;
 
-        atomic_write(&log_path, new_content.as_bytes())?;
+    let _lock = FileLock::acquire()?;
+    let config = Config::load()?;
+    let fmt = config.date_fmt()?;
+    let mut state = State::load()?;
+
+    // Merging into a tag that's in use is fine as long as no number clashes;
+    // a clash would leave two tasks with one ID and links to both
+    let index = TaskIndex::open(&config)?;
+    let (old_numbers, new_numbers) = (index.numbers(old_tag), index.numbers(new_tag));
+    let clashes: Vec<String> = old_numbers
+        .intersection(&new_numbers)
+        .map(|n| format!("{}-{}", new_tag, n))
+        .collect();
+    if !clashes.is_empty() {
+        return Err(TlError::Other(format!(
+            "{} already exists, so renaming '{}' to '{}' would duplicate it; retag one of them first",
+            clashes.join(", "),
+            old_tag,
+            new_tag
+        )));
     }
 
+    let prefix = format!("{}-", old_tag);
+    let (changes, rewrites) = rewrite_links_in_files(&config, &fmt, |id| {
+        let number = id.strip_prefix(&prefix)?;
+        Some(format!("{}-{}", new_tag, number))
+    })?;
+
+    // Every task line with the old tag changes, so no such change means no task
+    let found_any = changes
+        .iter()
+        .any(|c| parser::parse_task_line(&c.before, &fmt).is_some_and(|t| t.tag == old_tag));
     if !found_any {
         return Err(TlError::Other(format!("tag '{}' not found in any log file", old_tag)));
     }
+    if dry_run {
+        return Ok(changes);
+    }
 
-    // Update state: move counter from old tag to new tag
-    let old_counter = state.tags.remove(old_tag).unwrap_or(0);
-    let new_counter = state.tags.entry(new_tag.to_string()).or_insert(0);
-    if old_counter > *new_counter {
-        *new_counter = old_counter;
+    for (log_path, lines) in rewrites {
+        write_lines(&log_path, lines)?;
     }
+
+    // Update state: move counter from old tag to new tag, which now holds
+    // the numbers of both
+    let old_counter = state.tags.remove(old_tag).unwrap_or(0);
+    let in_use = old_numbers.union(&new_numbers).last().copied().unwrap_or(0);
+    state.sync_min(new_tag, old_counter.max(in_use));
     state.save()?;
 
-    Ok(())
+    Ok(changes)
 }
 
-/// Toggle priority on a task by its ID.
-pub fn toggle_priority(id: &str) -> Result<bool> {
+/// Move one task to `new_tag` under a freshly allocated ID. A "was <old id>"
+/// alias note is added under it, and references to the old ID on task and
+/// note lines in every file are rewritten. Subtasks keep their own IDs.
+/// Returns the new ID.
+pub fn retag_task(id: &str, new_tag: &str) -> Result<String> {
+    validate_tag(new_tag)?;
+
     let _lock = FileLock::acquire()?;
     let config = Config::load()?;
+    let fmt = config.date_fmt()?;
+    let mut state = State::load()?;
 
-    let log_path = router::find_file_for_task(&config, id)?;
-    let content = std::fs::read_to_string(&log_path)?;
-
-    let sections = parser::parse_log(&content, config.scan_window_lines);
+    let (log_path, content, sections) = load_task_file(&config, &fmt, id)?;
     let task = parser::find_task(&sections, id)?;
+    if task.tag == new_tag {
+        return Err(TlError::Other(format!("{} is already tagged {}", id, new_tag)));
+    }
+    router::check_tag_for_file(&config, new_tag, &log_path)?;
+
+    let number = allocate_number(&config, &mut state, new_tag)?;
+    let new_id = format!("{}-{}", new_tag, number);
+    let (_, mut rewrites) =
+        rewrite_links_in_files(&config, &fmt, |link| (link == id).then(|| new_id.clone()))?;
 
+    // The task's own line always changes, so its file is among the rewrites
+    if let Some((_, lines)) = rewrites.iter_mut().find(|(p, _)| *p == log_path) {
+        add_alias_note(lines, task, &note_indent(&config, &content), &fmt);
+    }
+
+    for (path, lines) in rewrites {
+        write_lines(&path, lines)?;
+    }
+    state.save()?;
+    Ok(new_id)
+}
+
+/// Add a "was <id>" note after the last note of `task`, so it can still be
+/// found by the ID it had when `lines` was parsed. `unit` is the file's note
+/// indentation.
+fn add_alias_note(lines: &mut Vec<String>, task: &parser::Task, unit: &str, fmt: &DateFormat) {
+    let insert_after = task.notes.last().map_or(task.line_number, |n| n.line_number);
+    let alias = parser::Note {
+        created_at: Some(dates::now()),
+        text: format!("was {}", task.id()),
+        ..Default::default()
+    }
+    .render(&format!("{}{}", task.indent, unit), fmt);
+    lines.insert(insert_after + 1, alias);
+}
+
+/// Give the task with ID `id` on `line` of `path` a fresh number for its
+/// tag. Used to resolve duplicate IDs, so the task is addressed by position
+/// rather than by ID. Returns the new ID.
+pub fn renumber_task(path: &Path, line: usize, id: &str) -> Result<String> {
+    let _lock = FileLock::acquire()?;
+    let config = Config::load()?;
+    let fmt = config.date_fmt()?;
+    let mut state = State::load()?;
+
+    let content = std::fs::read_to_string(path)?;
     let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
-    let line = &lines[task.line_number];
+    let new_id = renumber_line(&mut lines, line, id, &fmt, |tag| {
+        allocate_number(&config, &mut state, tag)
+    })?;
+    write_lines(path, lines)?;
+    state.save()?;
+    Ok(new_id)
+}
 
-    let new_priority = !task.priority;
+/// Give the task `id` on `lines[line]` the number `allocate` hands out for
+/// its tag, leaving the rest of the line alone. Returns the new ID.
+fn renumber_line(
+    lines: &mut [String],
+    line: usize,
+    id: &str,
+    fmt: &DateFormat,
+    allocate: impl FnOnce(&str) -> Result<u64>,
+) -> Result<String> {
+    let task = lines
+        .get(line)
+        .and_then(|l| parser::parse_task_line(l, fmt))
+        .filter(|t| t.id() == id)
+        .ok_or_else(|| {
+            TlError::Other(format!("line {} no longer holds task {}", line + 1, id))
+        })?;
+
+    let number = allocate(&task.tag)?;
+    let renumbered = parser::Task { number, ..task };
+    lines[line] = renumbered.render(fmt);
+    Ok(renumbered.id())
+}
 
-    // Rebuild the task line with or without priority marker
-    let status = if task.done { "x" } else { " " };
-    let priority_marker = if new_priority { "!" } else { "" };
+/// Toggle priority on a task by its ID: any level clears it, none makes it
+/// high. Returns the new level.
+pub fn toggle_priority(id: &str) -> Result<Priority> {
+    set_priority_with(id, |current| match current {
+        Priority::None => Priority::High,
+        _ => Priority::None,
+    })
+}
 
-    // Preserve any trailing content like completion timestamps
-    // The original line after the title might have " (timestamp)" appended
-    let original_title_and_rest = if task.done {
-        // For done tasks, the line might be: "- [x] tag-N! title (timestamp)"
-        // We need to preserve the timestamp part
-        let task_id_with_priority = if task.priority {
-            format!("{}!", task.id())
-        } else {
-            task.id()
-        };
-        let after_id = line
-            .find(&task_id_with_priority)
-            .map(|pos| &line[pos + task_id_with_priority.len()..])
-            .unwrap_or("");
-        // after_id starts with " title (timestamp)" or " title"
-        after_id.trim_start().to_string()
-    } else {
-        task.title.clone()
-    };
+/// Set a task's priority level.
+pub fn set_priority(id: &str, priority: Priority) -> Result<()> {
+    set_priority_with(id, |_| priority).map(|_| ())
+}
 
-    let new_line = format!(
-        "{}- [{}] {}{} {}",
-        task.indent, status, task.id(), priority_marker, original_title_and_rest
-    );
+/// Replace a task's priority with `f(current)`. Returns the new level.
+fn set_priority_with(id: &str, f: impl FnOnce(Priority) -> Priority) -> Result<Priority> {
+    let _lock = FileLock::acquire()?;
+    let config = Config::load()?;
+    let fmt = config.date_fmt()?;
 
-    lines[task.line_number] = new_line;
+    let (log_path, content, sections) = load_task_file(&config, &fmt, id)?;
+    let task = parser::find_task(&sections, id)?;
+
+    let new_priority = f(task.priority);
+    let toggled = parser::Task {
+        priority: new_priority,
+        ..task.clone()
+    };
+
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+    lines[task.line_number] = toggled.render(&fmt);
 
     let mut new_content = lines.join("\n");
     if !new_content.ends_with('\n') {
@@ -601,9 +1140,29 @@ pub fn toggle_priority(id: &str) -> Result<bool> {
     Ok(new_priority)
 }
 
+/// Set or (with `None`) clear a task's due date.
+pub fn set_due(id: &str, due: Option<NaiveDate>) -> Result<()> {
+    let _lock = FileLock::acquire()?;
+    let config = Config::load()?;
+    let fmt = config.date_fmt()?;
+
+    let (log_path, content, sections) = load_task_file(&config, &fmt, id)?;
+    let task = parser::find_task(&sections, id)?;
+
+    let updated = parser::Task {
+        title: parser::with_due(&task.title, due),
+        ..task.clone()
+    };
+
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+    lines[task.line_number] = updated.render(&fmt);
+    write_lines(&log_path, lines)
+}
+
 /// Get today's section text from all files.
 pub fn get_today() -> Result<String> {
     let config = Config::load()?;
+    let fmt = config.date_fmt()?;
     let files = config.effective_files();
     let paths = config.all_file_paths();
     let mut parts: Vec<String> = Vec::new();
@@ -614,7 +1173,7 @@ pub fn get_today() -> Result<String> {
             continue;
         }
         let content = std::fs::read_to_string(path)?;
-        if let Some(section) = parser::get_today_section_text(&content) {
+        if let Some(section) = parser::get_today_section_text(&content, &fmt) {
             if multi {
                 let label = files.get(i).map(|f| f.label.as_str()).unwrap_or("?");
                 parts.push(format!("[{}]\n{}", label, section));
@@ -632,16 +1191,28 @@ pub fn get_today() -> Result<String> {
 
 /// Search tasks within the scan window across all files.
 pub fn search(query: &str) -> Result<Vec<parser::Task>> {
+    Ok(search_with_files(query)?.into_iter().map(|(_, t)| t).collect())
+}
+
+/// Search tasks within the scan window across all files, each result paired
+/// with the label of the file it lives in.
+pub fn search_with_files(query: &str) -> Result<Vec<(String, parser::Task)>> {
     let config = Config::load()?;
+    let fmt = config.date_fmt()?;
     let mut results = Vec::new();
 
-    for path in config.all_file_paths() {
+    for entry in config.effective_files() {
+        let path = entry.resolved_path();
         if !path.exists() {
             continue;
         }
         let content = std::fs::read_to_string(&path)?;
-        let sections = parser::parse_log(&content, config.scan_window_lines);
-        results.extend(parser::search_tasks(&sections, query));
+        let sections = parser::parse_log(&content, config.scan_window_lines, &fmt);
+        results.extend(
+            parser::search_tasks(&sections, query)
+                .into_iter()
+                .map(|t| (entry.label.clone(), t)),
+        );
     }
 
     Ok(results)
@@ -649,19 +1220,351 @@ pub fn search(query: &str) -> Result<Vec<parser::Task>> {
 
 /// Parse all tasks from all files. Used by the TUI.
 pub fn all_tasks() -> Result<Vec<parser::Task>> {
+    Ok(all_tasks_with_files()?.into_iter().map(|(_, t)| t).collect())
+}
+
+/// Parse all tasks from all files, each paired with the label of the file it
+/// lives in.
+pub fn all_tasks_with_files() -> Result<Vec<(String, parser::Task)>> {
     let config = Config::load()?;
+    tasks_in_window(&config, &config.date_fmt()?)
+}
+
+/// Look up a single task by ID anywhere in the history, with the label of the
+/// file it lives in.
+pub fn get_task(id: &str) -> Result<(String, parser::Task)> {
+    let config = Config::load()?;
+    let fmt = config.date_fmt()?;
+    let (path, _, sections) = load_task_file(&config, &fmt, id)?;
+    let task = parser::find_task(&sections, id)?.clone();
+    let label = config
+        .effective_files()
+        .into_iter()
+        .find(|f| f.resolved_path() == path)
+        .map(|f| f.label)
+        .unwrap_or_default();
+    Ok((label, task))
+}
+
+/// The tasks needed to walk `id`'s dependency graph: everything within the
+/// scan window plus the task itself and any blockers of it (or of its
+/// blockers) that only exist further back in the history.
+pub fn dependency_pool(id: &str) -> Result<Vec<(String, parser::Task)>> {
+    let mut pool = all_tasks_with_files()?;
+    if !pool.iter().any(|(_, t)| t.id() == id) {
+        pool.push(get_task(id)?);
+    }
+    let mut pending = vec![id.to_string()];
+    let mut seen = HashSet::from([id.to_string()]);
+    while let Some(current) = pending.pop() {
+        let Some(task) = pool.iter().find(|(_, t)| t.id() == current).map(|(_, t)| t.clone()) else {
+            continue;
+        };
+        for blocker in &task.blocked_by {
+            if !pool.iter().any(|(_, t)| t.id() == *blocker) {
+                if let Ok(entry) = get_task(blocker) {
+                    pool.push(entry);
+                }
+            }
+        }
+        let refs: Vec<&parser::Task> = pool.iter().map(|(_, t)| t).collect();
+        for blocker in parser::blockers(&refs, &task) {
+            if seen.insert(blocker.id()) {
+                pending.push(blocker.id());
+            }
+        }
+    }
+    Ok(pool)
+}
+
+/// Every place another task mentions `id`, across the full history of all
+/// files (backlinks).
+pub fn task_references(id: &str) -> Result<Vec<parser::Reference>> {
+    let config = Config::load()?;
+    let fmt = config.date_fmt()?;
+    let index = TaskIndex::open(&config)?;
+    if let Err(TlError::TaskNotFound(e)) = index.find(id) {
+        return Err(TlError::TaskNotFound(e));
+    }
+
+    let labels: Vec<(PathBuf, String)> = config
+        .effective_files()
+        .into_iter()
+        .map(|f| (f.resolved_path(), f.label))
+        .collect();
+    let mut contents: std::collections::HashMap<PathBuf, String> = Default::default();
+    let mut refs = Vec::new();
+    for (path, entry, link) in index.references(id) {
+        if !contents.contains_key(&path) {
+            contents.insert(path.clone(), std::fs::read_to_string(&path)?);
+        }
+        let line = contents[&path].lines().nth(link.line).unwrap_or_default();
+        let in_note = link.line != entry.line;
+        let text = if in_note {
+            parser::is_note_line(line).map(|(_, note)| parser::parse_note_text(&note, &fmt).text)
+        } else {
+            parser::parse_task_line(line, &fmt).map(|task| task.title)
+        };
+        refs.push(parser::Reference {
+            from: entry.id.clone(),
+            file: labels
+                .iter()
+                .find(|(p, _)| *p == path)
+                .map(|(_, l)| l.clone())
+                .unwrap_or_default(),
+            line: link.line,
+            in_note,
+            text: text.unwrap_or_else(|| line.trim().to_string()),
+        });
+    }
+    Ok(refs)
+}
+
+/// Open tasks blocking `task`. Reverse `blocks:` markers are found within
+/// the scan window; `blocked-by:` IDs are looked up in the full history.
+fn open_blockers_of(config: &Config, fmt: &DateFormat, task: &parser::Task) -> Result<Vec<String>> {
+    let mut candidates: Vec<parser::Task> = tasks_in_window(config, fmt)?
+        .into_iter()
+        .map(|(_, t)| t)
+        .collect();
+    for id in &task.blocked_by {
+        if !candidates.iter().any(|t| t.id() == *id) {
+            if let Ok((_, _, sections)) = load_task_file(config, fmt, id) {
+                if let Ok(t) = parser::find_task(&sections, id) {
+                    candidates.push(t.clone());
+                }
+            }
+        }
+    }
+    let refs: Vec<&parser::Task> = candidates.iter().collect();
+    Ok(parser::open_blockers(&refs, task)
+        .into_iter()
+        .map(|t| t.id())
+        .collect())
+}
+
+/// Every task within the scan window of every file, with its file label.
+fn tasks_in_window(config: &Config, fmt: &DateFormat) -> Result<Vec<(String, parser::Task)>> {
     let mut tasks = Vec::new();
 
-    for path in config.all_file_paths() {
+    for entry in config.effective_files() {
+        let path = entry.resolved_path();
         if !path.exists() {
             continue;
         }
         let content = std::fs::read_to_string(&path)?;
-        let sections = parser::parse_log(&content, config.scan_window_lines);
+        let sections = parser::parse_log(&content, config.scan_window_lines, fmt);
         for sec in sections {
-            tasks.extend(sec.tasks);
+            tasks.extend(sec.tasks.into_iter().map(|t| (entry.label.clone(), t)));
         }
     }
 
     Ok(tasks)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fmt() -> DateFormat {
+        DateFormat::new("YYYY-MM-DD").unwrap()
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(|l| l.to_string()).collect()
+    }
+
+    /// Lift `id` out of `content` the way `move_task` does.
+    fn lift(content: &str, id: &str) -> (Vec<String>, Vec<String>) {
+        let sections = parser::parse_log(content, usize::MAX, &fmt());
+        let task = parser::find_task(&sections, id).unwrap();
+        lift_block(content, &sections, task, &fmt())
+    }
+
+    #[test]
+    fn lifting_takes_notes_and_subtasks_and_outdents() {
+        let content = "\
+### 2026-10-16
+
+- [ ] dev-1 parent
+  - [ ] dev-2 child
+    - [ ] dev-3 grandchild
+    - note on dev-2
+- [ ] dev-4 next
+";
+        let (remaining, block) = lift(content, "dev-2");
+        assert_eq!(
+            block,
+            lines("- [ ] dev-2 child\n  - [ ] dev-3 grandchild\n  - note on dev-2")
+        );
+        assert_eq!(
+            remaining,
+            lines("### 2026-10-16\n\n- [ ] dev-1 parent\n- [ ] dev-4 next")
+        );
+    }
+
+    #[test]
+    fn lifting_the_last_task_drops_its_date_section() {
+        let content = "\
+### Ideas
+
+- [ ] dev-1 someday
+
+### 2026-10-15
+
+- [ ] dev-2 moved
+    - with a note
+
+### 2026-10-16
+
+- [ ] dev-3 stays
+";
+        let (remaining, _) = lift(content, "dev-2");
+        assert_eq!(
+            remaining,
+            lines("### Ideas\n\n- [ ] dev-1 someday\n\n### 2026-10-16\n\n- [ ] dev-3 stays")
+        );
+        // Freeform headers stay even when nothing is left under them
+        let (remaining, _) = lift(content, "dev-1");
+        assert_eq!(&remaining[..3], lines("### Ideas\n\n\n").as_slice());
+    }
+
+    #[test]
+    fn dropping_sections() {
+        // Only blank lines left: the header goes, up to the next one
+        let mut l = lines("### 2026-10-15\n\n### 2026-10-16\n\n- [ ] dev-1 a");
+        drop_empty_section(&mut l, 0);
+        assert_eq!(l, lines("### 2026-10-16\n\n- [ ] dev-1 a"));
+        // At the end of the file the blank separator before it goes too
+        let mut l = lines("### 2026-10-15\n\n- [ ] dev-1 a\n\n### 2026-10-16\n");
+        drop_empty_section(&mut l, 4);
+        assert_eq!(l, lines("### 2026-10-15\n\n- [ ] dev-1 a"));
+        // Anything else under the header keeps it
+        let mut l = lines("### 2026-10-15\nsome thoughts\n### 2026-10-16");
+        drop_empty_section(&mut l, 0);
+        assert_eq!(l.len(), 3);
+    }
+
+    #[test]
+    fn reindent_swaps_leading_units() {
+        assert_eq!(reindent("\t\t- note", "\t", "    "), "        - note");
+        assert_eq!(reindent("     - note", "  ", "\t"), "\t\t - note");
+        assert_eq!(reindent("- [ ] dev-1 a", "\t", "  "), "- [ ] dev-1 a");
+        assert_eq!(reindent("  - note", "", "\t"), "  - note");
+    }
+
+    #[test]
+    fn moving_between_files_matches_the_destination_indent() {
+        let source = "### 2026-10-16\n\n- [ ] dev-1 a\n\t- [ ] dev-2 b\n\t\t- note on b\n";
+        let dest =
+            "### 2026-10-15\n\n- [ ] ops-1 x\n  - note on x\n\n### 2026-10-18\n\n- [ ] ops-2 y\n";
+        let (_, block) = lift(source, "dev-1");
+        let (from, to) = (
+            parser::detect_note_indent(source).unwrap(),
+            parser::detect_note_indent(dest).unwrap(),
+        );
+        let block = block.iter().map(|l| reindent(l, &from, &to)).collect();
+        let placed = place_block(
+            dest,
+            block,
+            date(2026, 10, 16),
+            &InsertPosition::Bottom,
+            &fmt(),
+        );
+        assert_eq!(
+            placed,
+            lines(
+                "### 2026-10-15\n\n- [ ] ops-1 x\n  - note on x\n\n\
+                 ### 2026-10-16\n- [ ] dev-1 a\n  - [ ] dev-2 b\n    - note on b\n\n\
+                 ### 2026-10-18\n\n- [ ] ops-2 y"
+            )
+        );
+    }
+
+    #[test]
+    fn link_rewrites_skip_alias_notes_and_freeform_text() {
+        let content = "\
+### 2026-10-16
+
+dev-1 came up in standup
+- [ ] ops-1 first
+    - was dev-1
+- [ ] dev-1 second, unlike ops-1
+    - see dev-1 and dev-10
+";
+        let (rewritten, changes) = rewrite_links_in_content(content, &fmt(), |id| {
+            (id == "dev-1").then(|| "ops-2".to_string())
+        });
+        assert_eq!(
+            rewritten,
+            lines(
+                "### 2026-10-16\n\ndev-1 came up in standup\n- [ ] ops-1 first\n    - was dev-1\n\
+                 - [ ] ops-2 second, unlike ops-1\n    - see ops-2 and dev-10"
+            )
+        );
+        let changed: Vec<usize> = changes.iter().map(|(line, _, _)| *line).collect();
+        assert_eq!(changed, [5, 6]);
+    }
+
+    #[test]
+    fn retagging_adds_the_alias_after_the_rewritten_notes() {
+        let content = "\
+### 2026-10-16
+
+- [ ] dev-1 parent
+  - [ ] dev-2 child
+      - was dev-9
+      - blocks dev-1
+- [ ] dev-3 next
+";
+        let sections = parser::parse_log(content, usize::MAX, &fmt());
+        let task = parser::find_task(&sections, "dev-2").unwrap();
+        let (mut rewritten, _) = rewrite_links_in_content(content, &fmt(), |id| {
+            (id == "dev-2").then(|| "ops-4".to_string())
+        });
+        add_alias_note(&mut rewritten, task, "    ", &fmt());
+
+        assert_eq!(&rewritten[..6], lines(
+            "### 2026-10-16\n\n- [ ] dev-1 parent\n  - [ ] ops-4 child\n      - was dev-9\n      - blocks dev-1"
+        ).as_slice());
+        let alias = parser::is_note_line(&rewritten[6]).unwrap();
+        assert_eq!(alias.0, "      ");
+        assert_eq!(
+            parser::alias_of(&parser::parse_note_text(&alias.1, &fmt()).text),
+            Some("dev-2".to_string())
+        );
+        assert_eq!(rewritten[7], "- [ ] dev-3 next");
+    }
+
+    #[test]
+    fn renumbering_changes_only_the_number() {
+        let mut content =
+            lines("- [ ] dev-2 first\n- [x] dev-2!p2 second due:2026-10-20\n      - a note");
+        let new_id = renumber_line(&mut content, 1, "dev-2", &fmt(), |tag| {
+            assert_eq!(tag, "dev");
+            Ok(7)
+        })
+        .unwrap();
+
+        assert_eq!(new_id, "dev-7");
+        assert_eq!(content[0], "- [ ] dev-2 first");
+        let task = parser::parse_task_line(&content[1], &fmt()).unwrap();
+        assert_eq!(task.id(), "dev-7");
+        assert_eq!(task.title, "second due:2026-10-20");
+        assert_eq!(task.status, Status::Done);
+        assert_eq!(task.priority, Priority::Med);
+        assert_eq!(content[2], "      - a note");
+    }
+
+    #[test]
+    fn renumbering_a_moved_line_fails() {
+        let mut content = lines("- [ ] dev-3 moved in\n- [ ] dev-2 still here");
+        let err = renumber_line(&mut content, 0, "dev-2", &fmt(), |_| Ok(9));
+        assert!(err.is_err());
+        assert_eq!(content[0], "- [ ] dev-3 moved in");
+    }
+}